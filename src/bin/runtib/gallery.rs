@@ -0,0 +1,178 @@
+use super::program::{find_programs, Program};
+use super::{RunOptions, StdWriter, EXIT_ABORTED, EXIT_OUTPUT_LIMIT};
+use indoc::indoc;
+use std::env::current_exe;
+use std::fmt::Write;
+use std::fs::read_to_string;
+use std::path::Path;
+use try_in_browser::lang::{get_homepage, get_lang_names, get_version, LangWriter};
+use try_in_browser::permalink::Permalink;
+use try_in_browser::post::escape_html;
+
+const HEADER: &str = indoc!(
+    r#"
+    <!DOCTYPE html>
+    <html lang="en">
+
+    <head>
+        <meta charset="utf-8">
+        <title>Try In Browser! - Gallery</title>
+        <meta name="viewport" content="width=device-width, initial-scale=1, shrink-to-fit=no">
+        <style>
+            @font-face {
+                font-display: swap;
+                font-family: 'DejaVu Sans Mono';
+                font-style: normal;
+                font-weight: 400;
+                src:
+                    url(DejaVuSansMono.woff2) format('woff2'),
+                    url(DejaVuSansMono.woff) format('woff');
+            }
+            * {
+                font-family: "DejaVu Sans Mono", monospace;
+            }
+            body {
+                margin: 2vh 15vw;
+            }
+            pre {
+                background-color: #eee;
+                padding: 0.5em;
+                white-space: pre-wrap;
+                word-break: break-all;
+            }
+        </style>
+    </head>
+
+    <body>
+    <h1>Try In Browser! - Gallery</h1>
+    "#
+);
+
+const FOOTER: &str = indoc!(
+    r#"
+    </body>
+
+    </html>
+    "#
+);
+
+/// Runs every example in `spec_dir` and prints a static HTML gallery to stdout.
+/// Each example may have a `.desc` sibling with its description.
+/// Returns `false` if the gallery could not be generated.
+pub fn gallery(spec_dir: &Path, options: RunOptions) -> bool {
+    StdWriter::init_impls();
+    let exe = if let Ok(exe) = current_exe() {
+        exe
+    } else {
+        eprintln!("Error: Cannot locate the runtib executable");
+        return false;
+    };
+    let programs = if let Ok(programs) = find_programs(spec_dir) {
+        programs
+    } else {
+        eprintln!("Error: Error encountered while reading directory");
+        return false;
+    };
+    if programs.is_empty() {
+        eprintln!("Error: No programs found in {}", spec_dir.display());
+        return false;
+    }
+
+    let mut html = HEADER.to_string();
+    for &lang in get_lang_names() {
+        let examples: Vec<_> = programs.iter().filter(|p| p.lang == lang).collect();
+        if examples.is_empty() {
+            continue;
+        }
+        let homepage = get_homepage(lang).unwrap_or("");
+        let _res = writeln!(
+            html,
+            "<h2><a href=\"{}\">{}</a></h2>",
+            escape_html(homepage),
+            escape_html(lang)
+        );
+        for program in examples {
+            eprintln!("Running {}", program.name());
+            if let Some(section) = render_program(&exe, program, options) {
+                html += &section;
+            } else {
+                eprintln!("Error: Error encountered while reading {}", program.name());
+                return false;
+            }
+        }
+    }
+    html += FOOTER;
+    print!("{}", html);
+    true
+}
+
+fn render_program(exe: &Path, program: &Program, options: RunOptions) -> Option<String> {
+    let code = read_to_string(&program.path).ok()?;
+    let input = program.sibling("in").unwrap_or_default();
+    let arg = program.arg();
+    let permalink = Permalink {
+        lang_version: get_version(program.lang).unwrap_or_default().to_string(),
+        ..Permalink::new(program.lang, &code, &input, &arg)
+    };
+    let link = permalink.to_link();
+    let output = match program.run(exe, options).0 {
+        Ok(output) => {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            let trailer = match output.status.code() {
+                Some(0) => None,
+                Some(EXIT_ABORTED) => Some("[aborted]"),
+                Some(EXIT_OUTPUT_LIMIT) => Some("[output limit exceeded]"),
+                _ => Some("[interpreter crashed]"),
+            };
+            if let Some(trailer) = trailer {
+                if !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                text += trailer;
+            }
+            text
+        }
+        Err(err) => format!("[failed to run: {}]", err),
+    };
+
+    let mut section = String::new();
+    let _res = writeln!(
+        section,
+        "<h3 id=\"tib-{:016x}\">{}</h3>",
+        permalink.fingerprint(),
+        escape_html(&program.name())
+    );
+    if let Some(desc) = program.sibling("desc") {
+        for paragraph in desc.split("\n\n").filter(|p| !p.trim().is_empty()) {
+            let _res = writeln!(section, "<p>{}</p>", escape_html(paragraph.trim()));
+        }
+    }
+    let _res = writeln!(
+        section,
+        "<p>{} byte{}</p>",
+        code.len(),
+        if code.len() == 1 { "" } else { "s" }
+    );
+    let _res = writeln!(section, "<pre><code>{}</code></pre>", escape_html(&code));
+    if !input.is_empty() {
+        let _res = writeln!(section, "<p>Input</p>\n<pre>{}</pre>", escape_html(&input));
+    }
+    if !arg.is_empty() {
+        let _res = writeln!(
+            section,
+            "<p>Arguments: <code>{}</code></p>",
+            escape_html(&arg)
+        );
+    }
+    let _res = writeln!(
+        section,
+        "<p>Output</p>\n<pre>{}</pre>",
+        escape_html(&output)
+    );
+    let _res = writeln!(
+        section,
+        "<p><a href=\"{}\">Try in browser!</a></p>",
+        escape_html(&link)
+    );
+    Some(section)
+}
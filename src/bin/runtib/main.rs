@@ -0,0 +1,328 @@
+mod gallery;
+mod program;
+mod run_dir;
+mod watch;
+
+use std::env::args;
+use std::fs::{read, File};
+use std::io::{self, Read, Write};
+use std::panic;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use try_in_browser::import::import_url;
+use try_in_browser::lang::{interpret, LangWriter, Stats};
+use try_in_browser::permalink::Permalink;
+use try_in_browser::runtime::panic::describe as describe_panic;
+
+const USAGE: &str = "\
+Usage: runtib [options] <language> <sourcefile> [arg]
+       runtib --watch [options] <language> <sourcefile> [arg]
+       runtib [options] run-dir <directory>
+       runtib [options] gallery <directory> > gallery.html
+       runtib --fingerprint <language> <sourcefile>
+       runtib --import-url <url>
+
+Options:
+-i <inputfile>          Read stdin from <inputfile> instead of the terminal
+--watch                 Re-run whenever <sourcefile> or <inputfile> changes
+--timeout <secs>        Abort the program after <secs> seconds
+--max-output <bytes>    Abort the program once stdout or stderr exceeds <bytes>
+--stats                 Print execution statistics to stderr after the run
+
+run-dir runs every program in <directory> whose extension maps to a language
+(e.g. .bf, .df, .slashes). Optional sibling files with the same stem provide
+stdin (.in), the argument (.args) and the expected stdout (.out).
+gallery runs the same kind of directory and prints a static HTML page showing
+each program with its description (.desc), output and permalink.
+--fingerprint prints the program fingerprint, the same one used by Postify.
+--import-url converts a Try It Online or Attempt This Online permalink
+to a TIB permalink and prints it.
+
+Exit codes:
+0    The program finished
+1    The program reported an error
+2    The program was aborted by --timeout
+3    The program was aborted by --max-output
+101  The interpreter crashed; the panic message and location are printed to stderr";
+
+const EXIT_ABORTED: i32 = 2;
+const EXIT_OUTPUT_LIMIT: i32 = 3;
+const EXIT_CRASHED: i32 = 101;
+
+#[derive(Clone, Copy, Default)]
+struct RunOptions {
+    timeout: Option<Duration>,
+    max_output: Option<usize>,
+    stats: bool,
+}
+
+struct StdWriter {
+    max_output: Option<usize>,
+    show_stats: bool,
+    out_len: usize,
+    err_len: usize,
+    start_time: Instant,
+}
+
+impl StdWriter {
+    const fn new(max_output: Option<usize>, show_stats: bool, start_time: Instant) -> Self {
+        Self {
+            max_output,
+            show_stats,
+            out_len: 0,
+            err_len: 0,
+            start_time,
+        }
+    }
+}
+
+impl LangWriter for StdWriter {
+    fn write_both(&mut self, out: &[u8], err: &[u8]) {
+        let limit = self.max_output.unwrap_or(usize::MAX);
+        let stdout_overflown = self.out_len + out.len() > limit;
+        let stderr_overflown = self.err_len + err.len() > limit;
+        if stdout_overflown || stderr_overflown {
+            let _res = io::stdout().write_all(truncate(out, limit.saturating_sub(self.out_len)));
+            let _res = io::stderr().write_all(truncate(err, limit.saturating_sub(self.err_len)));
+            stop(self.start_time, "output limit exceeded", EXIT_OUTPUT_LIMIT);
+        }
+        self.out_len += out.len();
+        self.err_len += err.len();
+        let _res = io::stdout().write_all(out);
+        let _res = io::stderr().write_all(err);
+    }
+    fn write_stats(&mut self, stats: &Stats) {
+        if self.show_stats {
+            let elapsed = self.start_time.elapsed().as_secs_f64();
+            let _res = io::stdout().flush();
+            eprint!("\n\nElapsed time: {:.6} sec\n{}\n", elapsed, stats);
+        }
+    }
+    fn terminate(&mut self) {
+        std::process::exit(0);
+    }
+    fn terminate_with_error(&mut self, msg: &str) {
+        eprint!("{}", msg);
+        std::process::exit(1);
+    }
+}
+
+fn main() {
+    let mut args = args();
+    let _arg = args.next(); // discard binary name
+    let mut watch_mode = false;
+    let mut fingerprint_mode = false;
+    let mut input_file = None;
+    let mut options = RunOptions::default();
+    let lang = loop {
+        match args.next() {
+            Some(opt) if opt == "--watch" => watch_mode = true,
+            Some(opt) if opt == "--stats" => options.stats = true,
+            Some(opt) if opt == "--fingerprint" => fingerprint_mode = true,
+            Some(opt) if opt == "-i" => {
+                if let Some(file) = args.next() {
+                    input_file = Some(file);
+                } else {
+                    eprintln!("Error: Missing input filename");
+                    return;
+                }
+            }
+            Some(opt) if opt == "--timeout" => {
+                let secs = args.next().and_then(|s| s.parse::<f64>().ok());
+                if let Some(secs) = secs.filter(|&secs| secs >= 0.0 && secs.is_finite()) {
+                    options.timeout = Some(Duration::from_secs_f64(secs));
+                } else {
+                    eprintln!("Error: --timeout needs a number of seconds");
+                    return;
+                }
+            }
+            Some(opt) if opt == "--max-output" => {
+                if let Some(bytes) = args.next().and_then(|s| s.parse::<usize>().ok()) {
+                    options.max_output = Some(bytes);
+                } else {
+                    eprintln!("Error: --max-output needs a number of bytes");
+                    return;
+                }
+            }
+            Some(opt) if opt == "--import-url" => {
+                if let Some(url) = args.next() {
+                    import(&url);
+                } else {
+                    eprintln!("Error: Missing URL");
+                }
+                return;
+            }
+            Some(lang) => break lang,
+            None => {
+                eprintln!("Error: Missing language name");
+                return;
+            }
+        }
+    };
+    if lang == "-h" {
+        println!("{}", USAGE);
+        return;
+    }
+    if lang == "run-dir" || lang == "gallery" {
+        let dir = if let Some(dir) = args.next() {
+            dir
+        } else {
+            eprintln!("Error: Missing directory name");
+            return;
+        };
+        let success = if lang == "run-dir" {
+            run_dir::run_dir(Path::new(&dir), options)
+        } else {
+            gallery::gallery(Path::new(&dir), options)
+        };
+        if !success {
+            std::process::exit(1);
+        }
+        return;
+    }
+    let file = if let Some(file) = args.next() {
+        file
+    } else {
+        eprintln!("Error: Missing source filename");
+        return;
+    };
+    let arg = args.next().unwrap_or_default();
+    if fingerprint_mode {
+        fingerprint(&lang, &file);
+        return;
+    }
+    if watch_mode {
+        watch::watch(&lang, &file, input_file.as_deref(), &arg, options);
+        return;
+    }
+    let pgm = if let Ok(pgm) = read(&file) {
+        pgm
+    } else {
+        eprintln!("Error: Error encountered while reading source code");
+        return;
+    };
+    let stdin = if let Some(stdin) = read_stdin(input_file.as_deref()) {
+        stdin
+    } else {
+        return;
+    };
+
+    run(lang, pgm, stdin, arg, options);
+}
+
+fn read_stdin(input_file: Option<&str>) -> Option<Vec<u8>> {
+    if let Some(input_file) = input_file {
+        let stdin = read(input_file).ok();
+        if stdin.is_none() {
+            eprintln!("Error: Error encountered while reading input file");
+        }
+        stdin
+    } else {
+        let mut stdin = vec![];
+        if io::stdin().read_to_end(&mut stdin).is_err() {
+            eprintln!("Error: Error encountered while reading stdin");
+            return None;
+        }
+        Some(stdin)
+    }
+}
+
+fn fingerprint(lang: &str, file: &str) {
+    let pgm = if let Ok(pgm) = read(file) {
+        pgm
+    } else {
+        eprintln!("Error: Error encountered while reading source code");
+        return;
+    };
+    let permalink = Permalink::new(lang, &pgm, "", "");
+    println!("{:016x}", permalink.fingerprint());
+}
+
+fn import(url: &str) {
+    StdWriter::init_impls();
+    match import_url(url) {
+        Ok(permalink) => println!("{}", permalink.to_link()),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the interpreter on a separate thread so that the main thread can enforce the timeout.
+/// Every way out of this function goes through `std::process::exit`.
+fn run(lang: String, pgm: Vec<u8>, stdin: Vec<u8>, arg: String, options: RunOptions) {
+    StdWriter::init_impls();
+    let start_time = Instant::now();
+    // Report crashes the same way as the web UI does
+    panic::set_hook(Box::new(|info| {
+        let _res = io::stdout().flush();
+        let msg = describe_panic(info.payload(), info.location());
+        let _res = writeln!(io::stderr(), "\n{}", msg);
+    }));
+    let (tx, rx) = channel();
+    thread::spawn(move || {
+        let mut writer = StdWriter::new(options.max_output, options.stats, start_time);
+        interpret(&lang, &pgm, &stdin, &arg, &mut writer);
+        let _res = tx.send(());
+    });
+    let result = options.timeout.map_or_else(
+        || rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        |timeout| rx.recv_timeout(timeout),
+    );
+    match result {
+        Ok(()) => std::process::exit(0),
+        Err(RecvTimeoutError::Timeout) => stop(start_time, "aborted", EXIT_ABORTED),
+        // The interpreter panicked; the panic hook has already reported it
+        Err(RecvTimeoutError::Disconnected) => {
+            stop(start_time, "interpreter crashed", EXIT_CRASHED)
+        }
+    }
+}
+
+/// Ends the run early, printing the same trailer as the web UI.
+fn stop(start_time: Instant, reason: &str, code: i32) -> ! {
+    let elapsed = start_time.elapsed().as_secs_f64();
+    let mut stdout = io::stdout().lock();
+    let mut stderr = io::stderr().lock();
+    let _res = stdout.flush();
+    let _res = writeln!(stderr, "\n\nElapsed time: {:.6} sec\n{}", elapsed, reason);
+    std::process::exit(code);
+}
+
+fn truncate(bytes: &[u8], max_len: usize) -> &[u8] {
+    &bytes[..bytes.len().min(max_len)]
+}
+
+/// Builds a command that runs a single program in a child `runtib` process,
+/// so that it can be killed or timed independently of the caller.
+fn runtib_command(
+    exe: &Path,
+    lang: &str,
+    file: &Path,
+    input_file: Option<&Path>,
+    arg: &str,
+    options: RunOptions,
+) -> io::Result<Command> {
+    let stdin = match input_file {
+        Some(input_file) => Stdio::from(File::open(input_file)?),
+        None => Stdio::null(),
+    };
+    let mut command = Command::new(exe);
+    if let Some(timeout) = options.timeout {
+        command
+            .arg("--timeout")
+            .arg(timeout.as_secs_f64().to_string());
+    }
+    if let Some(max_output) = options.max_output {
+        command.arg("--max-output").arg(max_output.to_string());
+    }
+    if options.stats {
+        command.arg("--stats");
+    }
+    command.arg(lang).arg(file).arg(arg).stdin(stdin);
+    Ok(command)
+}
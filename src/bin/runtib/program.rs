@@ -0,0 +1,63 @@
+use super::{runtib_command, RunOptions};
+use std::fs::{read_dir, read_to_string};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::time::Instant;
+use try_in_browser::lang::get_lang_by_extension;
+
+/// A program file in a directory of examples, along with its optional sibling files
+/// (`.in` for stdin, `.args` for the argument, and so on).
+pub struct Program {
+    pub path: PathBuf,
+    pub lang: &'static str,
+}
+
+impl Program {
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().to_string())
+    }
+
+    pub fn sibling(&self, ext: &str) -> Option<String> {
+        read_to_string(self.path.with_extension(ext)).ok()
+    }
+
+    pub fn arg(&self) -> String {
+        self.sibling("args")
+            .unwrap_or_default()
+            .trim_end_matches(&['\r', '\n'][..])
+            .to_string()
+    }
+
+    /// Runs the program in a child `runtib` process, returning its output and the elapsed time.
+    pub fn run(&self, exe: &Path, options: RunOptions) -> (io::Result<Output>, f64) {
+        let input_file = self.path.with_extension("in");
+        let input_file = Some(input_file.as_path()).filter(|p| p.is_file());
+        let start_time = Instant::now();
+        let output = runtib_command(exe, self.lang, &self.path, input_file, &self.arg(), options)
+            .and_then(|mut c| c.output());
+        (output, start_time.elapsed().as_secs_f64())
+    }
+}
+
+/// Finds every file in `dir` whose extension maps to a language, sorted by path.
+pub fn find_programs(dir: &Path) -> io::Result<Vec<Program>> {
+    let mut programs = vec![];
+    for entry in read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let lang = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(get_lang_by_extension);
+        if let Some(lang) = lang {
+            programs.push(Program { path, lang });
+        }
+    }
+    programs.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+    Ok(programs)
+}
@@ -0,0 +1,134 @@
+use super::program::{find_programs, Program};
+use super::{RunOptions, StdWriter, EXIT_ABORTED, EXIT_OUTPUT_LIMIT};
+use std::env::current_exe;
+use std::path::Path;
+use try_in_browser::lang::LangWriter;
+
+struct Report {
+    name: String,
+    lang: &'static str,
+    verdict: Verdict,
+    elapsed: f64,
+}
+
+enum Verdict {
+    Pass,
+    Fail,
+    Finished,
+    Crashed(String),
+}
+
+impl Verdict {
+    const fn is_ok(&self) -> bool {
+        matches!(self, Self::Pass | Self::Finished)
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Pass => "pass".to_string(),
+            Self::Fail => "FAIL".to_string(),
+            Self::Finished => "finished".to_string(),
+            Self::Crashed(reason) => format!("ERROR ({})", reason),
+        }
+    }
+}
+
+/// Runs every recognized program in `dir` and prints a summary table.
+/// Returns `true` if no program failed or crashed.
+pub fn run_dir(dir: &Path, options: RunOptions) -> bool {
+    StdWriter::init_impls();
+    let exe = if let Ok(exe) = current_exe() {
+        exe
+    } else {
+        eprintln!("Error: Cannot locate the runtib executable");
+        return false;
+    };
+    let programs = if let Ok(programs) = find_programs(dir) {
+        programs
+    } else {
+        eprintln!("Error: Error encountered while reading directory");
+        return false;
+    };
+    if programs.is_empty() {
+        eprintln!("Error: No programs found in {}", dir.display());
+        return false;
+    }
+    let reports: Vec<Report> = programs
+        .iter()
+        .map(|program| run_program(&exe, program, options))
+        .collect();
+    print_table(&reports);
+    reports.iter().all(|report| report.verdict.is_ok())
+}
+
+fn run_program(exe: &Path, program: &Program, options: RunOptions) -> Report {
+    let expected = program.sibling("out");
+    let (output, elapsed) = program.run(exe, options);
+
+    let verdict = match output {
+        Err(err) => Verdict::Crashed(err.to_string()),
+        Ok(output) if !output.status.success() => Verdict::Crashed(match output.status.code() {
+            Some(EXIT_ABORTED) => "aborted".to_string(),
+            Some(EXIT_OUTPUT_LIMIT) => "output limit exceeded".to_string(),
+            _ => output.status.to_string(),
+        }),
+        Ok(output) => expected.map_or(Verdict::Finished, |expected| {
+            let actual = String::from_utf8_lossy(&output.stdout);
+            if actual.trim_end_matches('\n') == expected.trim_end_matches('\n') {
+                Verdict::Pass
+            } else {
+                Verdict::Fail
+            }
+        }),
+    };
+    Report {
+        name: program.name(),
+        lang: program.lang,
+        verdict,
+        elapsed,
+    }
+}
+
+fn print_table(reports: &[Report]) {
+    let header = ("Program", "Language", "Result", "Time");
+    let rows: Vec<_> = reports
+        .iter()
+        .map(|r| {
+            let elapsed = format!("{:.6} sec", r.elapsed);
+            (r.name.as_str(), r.lang, r.verdict.describe(), elapsed)
+        })
+        .collect();
+    let name_width = rows
+        .iter()
+        .map(|r| r.0.len())
+        .fold(header.0.len(), usize::max);
+    let lang_width = rows
+        .iter()
+        .map(|r| r.1.len())
+        .fold(header.1.len(), usize::max);
+    let result_width = rows
+        .iter()
+        .map(|r| r.2.len())
+        .fold(header.2.len(), usize::max);
+
+    println!(
+        "{:<4$}  {:<5$}  {:<6$}  {}",
+        header.0, header.1, header.2, header.3, name_width, lang_width, result_width
+    );
+    for (name, lang, result, elapsed) in &rows {
+        println!(
+            "{:<4$}  {:<5$}  {:<6$}  {}",
+            name, lang, result, elapsed, name_width, lang_width, result_width
+        );
+    }
+
+    let count = |f: fn(&Verdict) -> bool| reports.iter().filter(|r| f(&r.verdict)).count();
+    println!(
+        "\n{} programs: {} passed, {} failed, {} crashed, {} without expected output",
+        reports.len(),
+        count(|v| matches!(v, Verdict::Pass)),
+        count(|v| matches!(v, Verdict::Fail)),
+        count(|v| matches!(v, Verdict::Crashed(_))),
+        count(|v| matches!(v, Verdict::Finished)),
+    );
+}
@@ -0,0 +1,70 @@
+use super::{runtib_command, RunOptions, EXIT_ABORTED, EXIT_OUTPUT_LIMIT};
+use std::env::current_exe;
+use std::fs::metadata;
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread::sleep;
+use std::time::{Duration, Instant, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const RUN_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Runs the program in a child `runtib` process, and restarts it from scratch
+/// whenever the source or input file is modified.
+/// A run that is still going when a file changes is killed, just like Stop in the UI.
+pub fn watch(lang: &str, file: &str, input_file: Option<&str>, arg: &str, options: RunOptions) {
+    let exe = if let Ok(exe) = current_exe() {
+        exe
+    } else {
+        eprintln!("Error: Cannot locate the runtib executable");
+        return;
+    };
+    loop {
+        let stamps = (modified(file), input_file.map(modified));
+        print!("\x1b[2J\x1b[H");
+        // The child writes to the same stdout, so the buffer must be empty before it starts
+        let _res = io::stdout().flush();
+        eprintln!("[runtib] {} {} (watching for changes)\n", lang, file);
+        let command = runtib_command(
+            &exe,
+            lang,
+            Path::new(file),
+            input_file.map(Path::new),
+            arg,
+            options,
+        );
+        let mut child = match command.and_then(|mut c| c.spawn()) {
+            Ok(child) => Some(child),
+            Err(err) => {
+                eprintln!("Error: Failed to start the program: {}", err);
+                None
+            }
+        };
+        let start_time = Instant::now();
+        while (modified(file), input_file.map(modified)) == stamps {
+            if let Some(status) = child.as_mut().and_then(|c| c.try_wait().ok().flatten()) {
+                let elapsed = start_time.elapsed().as_secs_f64();
+                match status.code() {
+                    // The child has already printed its own trailer
+                    Some(EXIT_ABORTED | EXIT_OUTPUT_LIMIT) => (),
+                    Some(0) => eprintln!("\n\nElapsed time: {:.6} sec\nfinished", elapsed),
+                    _ => eprintln!("\n\nElapsed time: {:.6} sec\nerror ({})", elapsed, status),
+                }
+                child = None;
+            }
+            sleep(if child.is_some() {
+                RUN_POLL_INTERVAL
+            } else {
+                POLL_INTERVAL
+            });
+        }
+        if let Some(mut child) = child {
+            let _res = child.kill();
+            let _res = child.wait();
+        }
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -0,0 +1,75 @@
+#![allow(clippy::must_use_candidate)]
+use std::fmt::Write;
+use std::str;
+
+/// Whether `bytes` can only be shown with escapes.
+pub fn needs_escapes(bytes: &[u8]) -> bool {
+    str::from_utf8(bytes).is_err()
+}
+
+/// The text to show for `bytes` in a text field.
+///
+/// With escapes, every byte that is not part of valid UTF-8 is written as `\xhh` and every
+/// backslash as `\\`, so any bytes can be edited. Without them the text is shown as is, which
+/// keeps backslash-heavy code readable, but invalid UTF-8 is replaced with U+FFFD; turn escapes
+/// on when `needs_escapes` says so.
+pub fn to_text(bytes: &[u8], escapes: bool) -> String {
+    if !escapes {
+        return String::from_utf8_lossy(bytes).into_owned();
+    }
+    let mut text = String::with_capacity(bytes.len());
+    let mut rest = bytes;
+    loop {
+        match str::from_utf8(rest) {
+            Ok(valid) => {
+                text += &valid.replace('\\', "\\\\");
+                return text;
+            }
+            Err(err) => {
+                let (valid, invalid) = rest.split_at(err.valid_up_to());
+                let valid = str::from_utf8(valid).unwrap_or_default();
+                text += &valid.replace('\\', "\\\\");
+                let len = err.error_len().unwrap_or(invalid.len());
+                for byte in &invalid[..len] {
+                    let _res = write!(text, "\\x{:02x}", byte);
+                }
+                rest = &invalid[len..];
+            }
+        }
+    }
+}
+
+/// The bytes written in `text`. With escapes, `\\` and `\xhh` are decoded,
+/// and a backslash starting anything else is kept as is.
+pub fn from_text(text: &str, escapes: bool) -> Vec<u8> {
+    if !escapes {
+        return text.as_bytes().to_vec();
+    }
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'\\' {
+            bytes.push(byte);
+        } else if let Some(tail) = rest.strip_prefix(b"\\") {
+            bytes.push(b'\\');
+            rest = tail;
+        } else if let Some(escaped) = hex_escape(rest) {
+            bytes.push(escaped);
+            rest = &rest[3..];
+        } else {
+            bytes.push(b'\\');
+        }
+    }
+    bytes
+}
+
+/// Parses the `xhh` following a backslash.
+fn hex_escape(rest: &[u8]) -> Option<u8> {
+    match rest {
+        [b'x', hi, lo, ..] if hi.is_ascii_hexdigit() && lo.is_ascii_hexdigit() => {
+            u8::from_str_radix(str::from_utf8(&rest[1..3]).ok()?, 16).ok()
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,274 @@
+#![allow(clippy::must_use_candidate)]
+use crate::lang::get_lang_by_import_id;
+use crate::permalink::Permalink;
+use data_encoding::BASE64_NOPAD;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+use std::fmt;
+
+const MAX_DECODED_SIZE: usize = 1 << 24;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportError {
+    UnsupportedUrl,
+    InvalidData,
+    UnknownLanguage(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedUrl => write!(f, "not a Try It Online or Attempt This Online link"),
+            Self::InvalidData => write!(f, "the link data is corrupted"),
+            Self::UnknownLanguage(id) => write!(f, "language `{}` is not available on TIB", id),
+        }
+    }
+}
+
+/// Converts a TIO or ATO permalink to a TIB permalink.
+///
+/// Both `https://tio.run/##...` and `https://ato.pxeger.com/run?1=...` links are accepted.
+/// Everything is decoded from the URL itself, so this works offline.
+///
+/// Language ids are looked up with `lang::get_lang_by_import_id`,
+/// so `LangWriter::init_impls` must have been called beforehand.
+///
+/// # Errors
+///
+/// Returns an error if the URL is not a TIO/ATO permalink, if its data is corrupted,
+/// or if its language is not available on TIB.
+pub fn import_url(url: &str) -> Result<Permalink, ImportError> {
+    let url = url.trim();
+    let url = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    if let Some(hash) = url.strip_prefix("tio.run/") {
+        import_tio(hash)
+    } else if let Some(query) = url.strip_prefix("ato.pxeger.com/run?") {
+        import_ato(query)
+    } else {
+        Err(ImportError::UnsupportedUrl)
+    }
+}
+
+/// TIO stores the language id, header, code, footer and input as UTF-8 strings separated by
+/// `\xff`, optionally followed by the flag, option and argument lists, each introduced by
+/// `\xfe` and with items separated by `\xff`. The whole thing is raw-deflated and base64-encoded.
+fn import_tio(hash: &str) -> Result<Permalink, ImportError> {
+    if let Some(data) = hash.strip_prefix("##") {
+        let state = inflate(data)?;
+        let mut sections = state.split(|&b| b == 0xfe);
+        let mut fields = sections.next().unwrap_or_default().split(|&b| b == 0xff);
+        let mut next = || fields.next().unwrap_or_default();
+        let (lang_id, header, code, footer, input) = (next(), next(), next(), next(), next());
+        let mut args = vec![];
+        // Compiler flags only make sense for compiled languages, so they are skipped
+        for section in sections.skip(1) {
+            for arg in section.split(|&b| b == 0xff).filter(|arg| !arg.is_empty()) {
+                args.push(utf8(arg)?);
+            }
+        }
+        to_permalink(&utf8(lang_id)?, header, code, footer, input, &args)
+    } else if let Some(lang_id) = hash.strip_prefix('#') {
+        to_permalink(lang_id, b"", b"", b"", b"", &[])
+    } else {
+        Err(ImportError::UnsupportedUrl)
+    }
+}
+
+/// ATO stores a `MessagePack` map of the program fields, raw-deflated and base64url-encoded
+/// in the `1=` query parameter (`1` being the format version).
+fn import_ato(query: &str) -> Result<Permalink, ImportError> {
+    let data = query
+        .split('&')
+        .find_map(|param| param.strip_prefix("1="))
+        .ok_or(ImportError::UnsupportedUrl)?;
+    let state = inflate(data)?;
+    let map = match msgpack::decode(&state) {
+        Some(msgpack::Value::Map(map)) => map,
+        _ => return Err(ImportError::InvalidData),
+    };
+    let field = |key: &str| -> Result<Vec<u8>, ImportError> {
+        let encoding = map
+            .get(&format!("{}Encoding", key))
+            .and_then(|v| v.as_str());
+        match (map.get(key), encoding) {
+            (None, _) => Ok(vec![]),
+            (Some(msgpack::Value::Str(s)), Some("base64")) => BASE64_NOPAD
+                .decode(s.trim_end_matches('=').as_bytes())
+                .map_err(|_| ImportError::InvalidData),
+            (Some(msgpack::Value::Str(s)), _) => Ok(s.clone().into_bytes()),
+            (Some(msgpack::Value::Bin(bytes)), _) => Ok(bytes.clone()),
+            (Some(_), _) => Err(ImportError::InvalidData),
+        }
+    };
+    let lang_id = field("language")?;
+    let (header, code, footer, input) = (
+        field("header")?,
+        field("code")?,
+        field("footer")?,
+        field("input")?,
+    );
+    let args = match map.get("arguments") {
+        Some(msgpack::Value::Array(args)) => args
+            .iter()
+            .map(|arg| {
+                arg.as_str()
+                    .map(ToString::to_string)
+                    .ok_or(ImportError::InvalidData)
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => vec![],
+    };
+    to_permalink(&utf8(&lang_id)?, &header, &code, &footer, &input, &args)
+}
+
+/// The code and the input are kept as raw bytes; the other fields must be UTF-8.
+fn to_permalink(
+    lang_id: &str,
+    header: &[u8],
+    code: &[u8],
+    footer: &[u8],
+    input: &[u8],
+    args: &[String],
+) -> Result<Permalink, ImportError> {
+    let lang = get_lang_by_import_id(lang_id)
+        .ok_or_else(|| ImportError::UnknownLanguage(lang_id.to_string()))?;
+    Ok(Permalink {
+        header: utf8(header)?,
+        footer: utf8(footer)?,
+        ..Permalink::new(lang, code, input, &args.join(" "))
+    })
+}
+
+/// Decodes base64 in any of the alphabets used by TIO (`@` for `+`) and ATO (base64url),
+/// then inflates the raw deflate stream.
+fn inflate(data: &str) -> Result<Vec<u8>, ImportError> {
+    let data: String = data
+        .trim_end_matches('=')
+        .chars()
+        .map(|c| match c {
+            '@' | '-' => '+',
+            '_' => '/',
+            c => c,
+        })
+        .collect();
+    let compressed = BASE64_NOPAD
+        .decode(data.as_bytes())
+        .map_err(|_| ImportError::InvalidData)?;
+    decompress_to_vec_with_limit(&compressed, MAX_DECODED_SIZE)
+        .map_err(|_| ImportError::InvalidData)
+}
+
+fn utf8(bytes: &[u8]) -> Result<String, ImportError> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| ImportError::InvalidData)
+}
+
+/// Just enough of `MessagePack` to read ATO links.
+mod msgpack {
+    use std::collections::HashMap;
+    use std::convert::TryInto;
+
+    pub enum Value {
+        // Numbers, booleans and nil are never needed, so their values are not kept
+        Other,
+        Str(String),
+        Bin(Vec<u8>),
+        Array(Vec<Self>),
+        Map(HashMap<String, Self>),
+    }
+
+    impl Value {
+        pub fn as_str(&self) -> Option<&str> {
+            match self {
+                Self::Str(s) => Some(s),
+                _ => None,
+            }
+        }
+    }
+
+    pub fn decode(mut buf: &[u8]) -> Option<Value> {
+        let value = read_value(&mut buf)?;
+        if buf.is_empty() {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    fn take<'a>(buf: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+        if buf.len() < len {
+            return None;
+        }
+        let (head, tail) = buf.split_at(len);
+        *buf = tail;
+        Some(head)
+    }
+
+    fn read_uint(buf: &mut &[u8], len: usize) -> Option<u64> {
+        Some(
+            take(buf, len)?
+                .iter()
+                .fold(0, |n, &b| n << 8 | u64::from(b)),
+        )
+    }
+
+    fn read_len(buf: &mut &[u8], len: usize) -> Option<usize> {
+        read_uint(buf, len)?.try_into().ok()
+    }
+
+    fn read_str(buf: &mut &[u8], len: usize) -> Option<Value> {
+        let bytes = take(buf, len)?;
+        String::from_utf8(bytes.to_vec()).ok().map(Value::Str)
+    }
+
+    fn read_array(buf: &mut &[u8], len: usize) -> Option<Value> {
+        (0..len)
+            .map(|_| read_value(buf))
+            .collect::<Option<_>>()
+            .map(Value::Array)
+    }
+
+    fn read_map(buf: &mut &[u8], len: usize) -> Option<Value> {
+        let mut map = HashMap::new();
+        for _ in 0..len {
+            let key = match read_value(buf)? {
+                Value::Str(key) => key,
+                _ => return None,
+            };
+            map.insert(key, read_value(buf)?);
+        }
+        Some(Value::Map(map))
+    }
+
+    fn read_value(buf: &mut &[u8]) -> Option<Value> {
+        let tag = *take(buf, 1)?.first()?;
+        match tag {
+            0x00..=0x7f | 0xc0 | 0xc2 | 0xc3 | 0xe0..=0xff => Some(Value::Other),
+            0x80..=0x8f => read_map(buf, usize::from(tag & 0x0f)),
+            0x90..=0x9f => read_array(buf, usize::from(tag & 0x0f)),
+            0xa0..=0xbf => read_str(buf, usize::from(tag & 0x1f)),
+            0xc4..=0xc6 => {
+                let len = read_len(buf, 1 << (tag - 0xc4))?;
+                Some(Value::Bin(take(buf, len)?.to_vec()))
+            }
+            0xca => take(buf, 4).map(|_| Value::Other),
+            0xcb => take(buf, 8).map(|_| Value::Other),
+            0xcc..=0xcf => take(buf, 1 << (tag - 0xcc)).map(|_| Value::Other),
+            0xd0..=0xd3 => take(buf, 1 << (tag - 0xd0)).map(|_| Value::Other),
+            0xd9..=0xdb => {
+                let len = read_len(buf, 1 << (tag - 0xd9))?;
+                read_str(buf, len)
+            }
+            0xdc | 0xdd => {
+                let len = read_len(buf, 2 << (tag - 0xdc))?;
+                read_array(buf, len)
+            }
+            0xde | 0xdf => {
+                let len = read_len(buf, 2 << (tag - 0xde))?;
+                read_map(buf, len)
+            }
+            _ => None,
+        }
+    }
+}
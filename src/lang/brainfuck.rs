@@ -2,10 +2,13 @@ use std::collections::HashMap;
 
 use indoc::indoc;
 
-use super::LangWriter;
+use super::{LangWriter, Stats};
 
 pub const NAME: &str = "brainfuck";
 pub const HOMEPAGE: &str = "https://esolangs.org/wiki/Brainfuck";
+pub const EXTENSION: &str = "bf";
+pub const IMPORT_IDS: &[&str] = &["brainfuck"];
+pub const VERSION: &str = "1.0.0";
 pub const HELP: &str = indoc!(
     r#"
     brainfuck (https://esolangs.org/wiki/Brainfuck)
@@ -17,29 +20,21 @@ pub const HELP: &str = indoc!(
     -    Increment cell
     >    Move pointer right
     <    Move pointer left (error on out of bounds)
-    .    Output character value of cell
+    .    Output the value of cell as a byte
     ,    Read a character as an integer (0 on EOF)
     [    Start of while loop
     ]    End of loop
     "#
 );
 
-pub fn interpret<T: LangWriter>(pgm_str: &str, input_str: &str, _args: &str, writer: &mut T) {
-    let pgm = String::from(pgm_str).into_bytes();
-    let mut input = input_str.bytes();
-
-    let mut pos = 0_usize;
-    let zeroes = vec![0_u8; 100];
-    let mut tape = zeroes.clone();
-
+/// Finds the matching `]` of every `[`, or reports an error if the brackets don't match.
+fn match_loops<T: LangWriter>(pgm: &[u8], writer: &mut T) -> Option<HashMap<usize, usize>> {
     //Keys are the indices of loop starts, values are indices of loop ends
     let mut loops = HashMap::<usize, usize>::new();
     //The indices of the starts of the loops that it's currently in
-    //This `Vec` is reused for the actual interpreting too
     let mut loop_starts = Vec::<usize>::new();
 
     //Load the indices of the `[`'s and `]`'s into `loops`
-    //while ind < pgm.len() {
     for (ind, char) in pgm.iter().enumerate() {
         match char {
             b'[' => {
@@ -50,12 +45,11 @@ pub fn interpret<T: LangWriter>(pgm_str: &str, input_str: &str, _args: &str, wri
                     loops.insert(loop_start, ind);
                 } else {
                     writer.terminate_with_error(&*format!("Extra `]` found at index {}", ind));
-                    return;
+                    return None;
                 }
             }
             _ => {}
         }
-        //ind += 1;
     }
 
     //Handle unclosed loops
@@ -64,13 +58,39 @@ pub fn interpret<T: LangWriter>(pgm_str: &str, input_str: &str, _args: &str, wri
             "Error: Missing closing `]`'s to correspond with `[`'s at indices {:?}",
             loops
         ));
-        return;
+        return None;
     }
+    Some(loops)
+}
+
+pub fn interpret<T: LangWriter>(pgm: &[u8], input: &[u8], _args: &str, writer: &mut T) {
+    let mut input = input.iter().copied();
 
+    let mut pos = 0_usize;
+    let zeroes = vec![0_u8; 100];
+    let mut tape = zeroes.clone();
+
+    let loops = if let Some(loops) = match_loops(pgm, writer) {
+        loops
+    } else {
+        return;
+    };
+    //The indices of the starts of the loops that it's currently in
+    let mut loop_starts = Vec::<usize>::new();
+
+    let mut stats = Stats {
+        peak_tape: Some(1),
+        max_loop_depth: Some(0),
+        ..Stats::default()
+    };
     let mut ind = 0_usize;
     while ind < pgm.len() {
         let curr_cmd = pgm[ind];
         ind += 1;
+        if b"+-<>.,[]".contains(&curr_cmd) {
+            stats.steps += 1;
+            writer.progress(stats.steps, Some(ind - 1));
+        }
         match curr_cmd {
             b'+' => {
                 if tape[pos] == 255 {
@@ -91,9 +111,11 @@ pub fn interpret<T: LangWriter>(pgm_str: &str, input_str: &str, _args: &str, wri
                 if pos == tape.len() {
                     tape.extend(zeroes.iter());
                 }
+                stats.peak_tape = stats.peak_tape.max(Some(pos + 1));
             }
             b'<' => {
                 if pos == 0 {
+                    writer.write_stats(&stats);
                     writer.terminate_with_error(&*format!(
                         "Error on `<` at index {}: Reached left end of tape",
                         ind
@@ -103,8 +125,7 @@ pub fn interpret<T: LangWriter>(pgm_str: &str, input_str: &str, _args: &str, wri
                 pos -= 1;
             }
             b'.' => {
-                let out = tape[pos] as char;
-                writer.write_out(&*out.to_string());
+                writer.write_out([tape[pos]]);
             }
             b',' => {
                 tape[pos] = input.next().unwrap_or(0);
@@ -115,12 +136,13 @@ pub fn interpret<T: LangWriter>(pgm_str: &str, input_str: &str, _args: &str, wri
                     ind = loops[&(ind - 1)] + 1;
                 } else {
                     loop_starts.push(ind);
+                    stats.max_loop_depth = stats.max_loop_depth.max(Some(loop_starts.len()));
                 }
             }
             b']' => {
                 //Jump to the start of this loop, which is the last loop
                 //We can unwrap without fear because the loops have been
-                //checked in the previous while loop
+                //checked by `match_loops`
                 if tape[pos] == 0 {
                     loop_starts.pop();
                 } else {
@@ -130,4 +152,5 @@ pub fn interpret<T: LangWriter>(pgm_str: &str, input_str: &str, _args: &str, wri
             _ => {} //This is a comment, don't do anything
         }
     }
+    writer.write_stats(&stats);
 }
@@ -1,8 +1,11 @@
-use super::LangWriter;
+use super::{LangWriter, Stats};
 use indoc::indoc;
 
 pub const NAME: &str = "Deadfish";
 pub const HOMEPAGE: &str = "https://esolangs.org/wiki/Deadfish";
+pub const EXTENSION: &str = "df";
+pub const IMPORT_IDS: &[&str] = &["deadfish-", "deadfish", "deadfish_tilde"];
+pub const VERSION: &str = "1.0.0";
 pub const HELP: &str = indoc!(
     r#"
     Deadfish (https://esolangs.org/wiki/Deadfish)
@@ -13,10 +16,15 @@ pub const HELP: &str = indoc!(
     "#
 );
 
-pub fn interpret<T: LangWriter>(pgm: &str, _input: &str, args: &str, writer: &mut T) {
+pub fn interpret<T: LangWriter>(pgm: &[u8], _input: &[u8], args: &str, writer: &mut T) {
     let mut counter = 0_u32;
     let is_char_output = args == "-o";
-    for b in pgm.bytes() {
+    let mut stats = Stats::default();
+    for (ind, &b) in pgm.iter().enumerate() {
+        if b"idso".contains(&b) {
+            stats.steps += 1;
+            writer.progress(stats.steps, Some(ind));
+        }
         match b {
             b'i' => {
                 counter = if counter == 255 { 0 } else { counter + 1 };
@@ -33,13 +41,14 @@ pub fn interpret<T: LangWriter>(pgm: &str, _input: &str, args: &str, writer: &mu
             }
             b'o' => {
                 if is_char_output {
-                    writer.write_out(&((counter % 256) as u8 as char).to_string());
+                    writer.write_out([(counter % 256) as u8]);
                 } else {
-                    writer.write_out(&format!("{}\n", counter));
+                    writer.write_out(format!("{}\n", counter));
                 }
             }
             _ => (),
         }
     }
+    writer.write_stats(&stats);
     writer.terminate();
 }
@@ -3,6 +3,9 @@ use indoc::indoc;
 
 pub const NAME: &str = "ExampleLang";
 pub const HOMEPAGE: &str = "https://example.com";
+pub const EXTENSION: &str = "example";
+pub const IMPORT_IDS: &[&str] = &[];
+pub const VERSION: &str = "1.0.0";
 pub const HELP: &str = indoc!(
     r#"
     An example language for debugging purposes.
@@ -15,47 +18,50 @@ pub const HELP: &str = indoc!(
     "#
 );
 
-pub fn interpret<T: LangWriter>(pgm: &str, input: &str, args: &str, writer: &mut T) {
+pub fn interpret<T: LangWriter>(pgm: &[u8], input: &[u8], args: &str, writer: &mut T) {
     match pgm {
-        "lang" => interpret_lang(pgm, input, args, writer),
-        "slow" => interpret_slow(pgm, input, args, writer),
-        "crasher" => interpret_crasher(pgm, input, args, writer),
-        "looper" => interpret_looper(pgm, input, args, writer),
-        "talker" => interpret_talker(pgm, input, args, writer),
-        _ => writer.write_err(&format!("Unrecognized program: {}", pgm)),
+        b"lang" => interpret_lang(pgm, input, args, writer),
+        b"slow" => interpret_slow(pgm, input, args, writer),
+        b"crasher" => interpret_crasher(pgm, input, args, writer),
+        b"looper" => interpret_looper(pgm, input, args, writer),
+        b"talker" => interpret_talker(pgm, input, args, writer),
+        _ => writer.write_err(format!(
+            "Unrecognized program: {}",
+            String::from_utf8_lossy(pgm)
+        )),
     }
 }
 
-fn interpret_lang<T: LangWriter>(_pgm: &str, _input: &str, _args: &str, writer: &mut T) {
+fn interpret_lang<T: LangWriter>(_pgm: &[u8], _input: &[u8], _args: &str, writer: &mut T) {
     for i in 0..40 {
-        writer.write_both("S", &format!("{}", i));
+        writer.write_both(b"S", i.to_string().as_bytes());
     }
     writer.terminate();
 }
 
-fn interpret_slow<T: LangWriter>(_pgm: &str, _input: &str, _args: &str, writer: &mut T) {
+fn interpret_slow<T: LangWriter>(_pgm: &[u8], _input: &[u8], _args: &str, writer: &mut T) {
     for i in 0..400_000_000 {
         if i % 10_000_000 == 0 {
-            writer.write_both("S", &format!("{}", i / 10_000_000 % 10));
+            writer.write_both(b"S", (i / 10_000_000 % 10).to_string().as_bytes());
         }
     }
     writer.terminate();
 }
 
-fn interpret_crasher<T: LangWriter>(_pgm: &str, _input: &str, _args: &str, writer: &mut T) {
+fn interpret_crasher<T: LangWriter>(_pgm: &[u8], _input: &[u8], _args: &str, writer: &mut T) {
     for i in 0..400_000_000 {
         if i % 10_000_000 == 0 {
-            writer.write_both("S", &format!("{}", i / 10_000_000 % 10));
+            writer.write_both(b"S", (i / 10_000_000 % 10).to_string().as_bytes());
         }
     }
     panic!("wtf");
 }
 
-fn interpret_looper<T: LangWriter>(_pgm: &str, _input: &str, _args: &str, writer: &mut T) {
+fn interpret_looper<T: LangWriter>(_pgm: &[u8], _input: &[u8], _args: &str, writer: &mut T) {
     let mut i = 0;
     loop {
         if i % 10_000_000 == 0 {
-            writer.write_both("S", &format!("{}", i / 10_000_000 % 10));
+            writer.write_both(b"S", (i / 10_000_000 % 10).to_string().as_bytes());
             if i >= 100_000_000 {
                 i = 0;
             }
@@ -64,14 +70,14 @@ fn interpret_looper<T: LangWriter>(_pgm: &str, _input: &str, _args: &str, writer
     }
 }
 
-fn interpret_talker<T: LangWriter>(_pgm: &str, _input: &str, _args: &str, writer: &mut T) {
+fn interpret_talker<T: LangWriter>(_pgm: &[u8], _input: &[u8], _args: &str, writer: &mut T) {
     let mut i = 0;
     loop {
         if i % 100 == 0 {
             writer.write_out("S");
         }
         if i % 100_000 == 0 {
-            writer.write_err(&format!("{}", i / 100_000 % 10));
+            writer.write_err(format!("{}", i / 100_000 % 10));
             if i >= 100_000_000 {
                 i = 0;
             }
@@ -6,22 +6,30 @@ mod deadfish;
 mod example_lang;
 mod s10k;
 mod slashes;
+mod stats;
+
+pub use stats::Stats;
 
 use once_cell::sync::OnceCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use typemap::{Key, ShareMap};
 
 pub trait LangWriter: 'static + Sized {
     fn init_impls() {
         init_impls::<Self>();
     }
-    fn write_both(&mut self, out: &str, err: &str);
-    fn write_out(&mut self, out: &str) {
-        self.write_both(out, "");
+    /// Writes to stdout and stderr. Output is bytes, which need not be valid UTF-8.
+    fn write_both(&mut self, out: &[u8], err: &[u8]);
+    fn write_out(&mut self, out: impl AsRef<[u8]>) {
+        self.write_both(out.as_ref(), b"");
     }
-    fn write_err(&mut self, err: &str) {
-        self.write_both("", err);
+    fn write_err(&mut self, err: impl AsRef<[u8]>) {
+        self.write_both(b"", err.as_ref());
     }
+    fn write_stats(&mut self, _stats: &Stats) {}
+    /// Called by interpreters after every step, with the number of steps so far and the
+    /// position of the current instruction in the program, if there is one.
+    fn progress(&mut self, _steps: u64, _ip: Option<usize>) {}
     fn terminate(&mut self) {}
     fn terminate_with_error(&mut self, _msg: &str) {}
 }
@@ -30,15 +38,19 @@ struct KeyWrapper<T: LangWriter> {
     _content: T,
 }
 
-type Interpret<T> = fn(&str, &str, &str, &mut T);
+type Interpret<T> = fn(&[u8], &[u8], &str, &mut T);
 
 impl<T: LangWriter> Key for KeyWrapper<T> {
-    type Value = HashMap<&'static str, Interpret<T>>;
+    type Value = HashMap<(&'static str, &'static str), Interpret<T>>;
 }
 
 struct LangImpls {
     names: Vec<&'static str>,
     homepages: HashMap<&'static str, &'static str>,
+    extensions: HashMap<&'static str, &'static str>,
+    import_ids: HashMap<&'static str, &'static str>,
+    versions: HashMap<&'static str, &'static str>,
+    known_versions: HashSet<(&'static str, &'static str)>,
     helps: HashMap<&'static str, &'static str>,
     interprets: ShareMap,
 }
@@ -48,16 +60,39 @@ static IMPLS: OnceCell<LangImpls> = OnceCell::new();
 fn init_impls<T: LangWriter>() {
     let mut names = vec![];
     let mut homepages = HashMap::new();
+    let mut extensions = HashMap::new();
+    let mut import_ids = HashMap::new();
+    let mut versions = HashMap::new();
+    let mut known_versions = HashSet::new();
     let mut helps = HashMap::new();
     let mut interprets = ShareMap::custom();
     let mut interpret_inner = HashMap::new();
 
+    // When a language's semantics change, its previous implementation can be kept as a separate
+    // module and registered with `add_lang!(old_module, legacy)`, so that links made with the old
+    // version still run the same way.
     macro_rules! add_lang {
+        ($lang: ident, legacy) => {
+            known_versions.insert(($lang::NAME, $lang::VERSION));
+            interpret_inner.insert(
+                ($lang::NAME, $lang::VERSION),
+                $lang::interpret::<T> as Interpret<T>,
+            );
+        };
         ($lang: ident) => {
             names.push($lang::NAME);
+            versions.insert($lang::NAME, $lang::VERSION);
+            known_versions.insert(($lang::NAME, $lang::VERSION));
             homepages.insert($lang::NAME, $lang::HOMEPAGE);
+            extensions.insert($lang::EXTENSION, $lang::NAME);
+            for &id in $lang::IMPORT_IDS {
+                import_ids.insert(id, $lang::NAME);
+            }
             helps.insert($lang::NAME, $lang::HELP);
-            interpret_inner.insert($lang::NAME, $lang::interpret::<T> as Interpret<T>);
+            interpret_inner.insert(
+                ($lang::NAME, $lang::VERSION),
+                $lang::interpret::<T> as Interpret<T>,
+            );
         };
     }
 
@@ -75,6 +110,10 @@ fn init_impls<T: LangWriter>() {
     let _res = IMPLS.set(LangImpls {
         names,
         homepages,
+        extensions,
+        import_ids,
+        versions,
+        known_versions,
         helps,
         interprets,
     });
@@ -95,7 +134,48 @@ pub fn get_homepage(lang_name: &str) -> Option<&'static str> {
 }
 
 #[allow(clippy::missing_panics_doc)]
-pub fn interpret<T: LangWriter>(lang: &str, pgm: &str, input: &str, args: &str, writer: &mut T) {
+pub fn get_lang_by_extension(ext: &str) -> Option<&'static str> {
+    IMPLS.get().unwrap().extensions.get(ext).copied()
+}
+
+#[allow(clippy::missing_panics_doc)]
+pub fn get_lang_by_import_id(id: &str) -> Option<&'static str> {
+    IMPLS.get().unwrap().import_ids.get(id).copied()
+}
+
+#[allow(clippy::missing_panics_doc)]
+pub fn get_version(lang_name: &str) -> Option<&'static str> {
+    IMPLS.get().unwrap().versions.get(lang_name).copied()
+}
+
+#[allow(clippy::missing_panics_doc)]
+pub fn has_version(lang_name: &str, version: &str) -> bool {
+    IMPLS
+        .get()
+        .unwrap()
+        .known_versions
+        .contains(&(lang_name, version))
+}
+
+pub fn interpret<T: LangWriter>(
+    lang: &str,
+    pgm: impl AsRef<[u8]>,
+    input: impl AsRef<[u8]>,
+    args: &str,
+    writer: &mut T,
+) {
+    interpret_version(lang, None, pgm, input, args, writer);
+}
+
+#[allow(clippy::missing_panics_doc)]
+pub fn interpret_version<T: LangWriter>(
+    lang: &str,
+    version: Option<&str>,
+    pgm: impl AsRef<[u8]>,
+    input: impl AsRef<[u8]>,
+    args: &str,
+    writer: &mut T,
+) {
     T::init_impls();
     if args == "-h" {
         if let Some(help) = get_help(lang) {
@@ -105,12 +185,20 @@ pub fn interpret<T: LangWriter>(lang: &str, pgm: &str, input: &str, args: &str,
         }
     }
     let interprets = &IMPLS.get().unwrap().interprets;
-    if let Some(interpret) = interprets.get::<KeyWrapper<T>>().unwrap().get(lang) {
-        interpret(pgm, input, args, writer);
+    let version = version
+        .filter(|&version| has_version(lang, version))
+        .or_else(|| get_version(lang));
+    let interpret = version.and_then(|version| {
+        interprets
+            .get::<KeyWrapper<T>>()
+            .unwrap()
+            .get(&(lang, version))
+    });
+    if let Some(interpret) = interpret {
+        interpret(pgm.as_ref(), input.as_ref(), args, writer);
         writer.terminate();
     } else {
         let err = format!("Unknown lang: {}", lang);
-        writer.write_err(&err);
         writer.terminate_with_error(&err);
     }
 }
@@ -3,6 +3,9 @@ use indoc::indoc;
 
 pub const NAME: &str = "S10K";
 pub const HOMEPAGE: &str = "https://try-in-browser.netlify.app/";
+pub const EXTENSION: &str = "s10k";
+pub const IMPORT_IDS: &[&str] = &[];
+pub const VERSION: &str = "1.0.0";
 pub const HELP: &str = indoc!(
     r#"
     S10K, the first TIB-original language.
@@ -10,7 +13,7 @@ pub const HELP: &str = indoc!(
     "#
 );
 
-pub fn interpret<T: LangWriter>(_pgm: &str, _input: &str, _args: &str, writer: &mut T) {
-    writer.write_out(&"S".repeat(10000));
+pub fn interpret<T: LangWriter>(_pgm: &[u8], _input: &[u8], _args: &str, writer: &mut T) {
+    writer.write_out("S".repeat(10000));
     writer.terminate();
 }
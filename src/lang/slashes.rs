@@ -1,9 +1,12 @@
 use indoc::indoc;
 
-use super::LangWriter;
+use super::{LangWriter, Stats};
 
 pub const NAME: &str = "///";
 pub const HOMEPAGE: &str = "https://esolangs.org/wiki////";
+pub const EXTENSION: &str = "slashes";
+pub const IMPORT_IDS: &[&str] = &["slashes"];
+pub const VERSION: &str = "1.0.0";
 pub const HELP: &str = indoc!(
     r#"
     /// (https://esolangs.org/wiki////)
@@ -16,28 +19,43 @@ pub const HELP: &str = indoc!(
     "#
 );
 
-pub fn interpret<T: LangWriter>(pgm_str: &str, _input: &str, _args: &str, writer: &mut T) {
+pub fn interpret<T: LangWriter>(pgm: &[u8], _input: &[u8], _args: &str, writer: &mut T) {
     let mut mode = Mode::Print;
-    let mut patt = String::new();
-    let mut repl = String::new();
+    let mut patt = vec![];
+    let mut repl = vec![];
+    // Printed bytes are collected so that characters are never split between writes
+    let mut out = vec![];
 
-    let mut pgm_str = pgm_str.to_string();
-    let mut pgm = pgm_str.chars();
+    let mut pgm = pgm.to_vec();
+    let mut ind = 0_usize;
+    let mut stats = Stats {
+        substitutions: Some(0),
+        ..Stats::default()
+    };
 
-    while let Some(chr) = pgm.next() {
-        if chr == '/' {
+    while let Some(&byte) = pgm.get(ind) {
+        ind += 1;
+        stats.steps += 1;
+        // `ind` points into the rewritten program, not the submitted code
+        writer.progress(stats.steps, None);
+        if byte == b'/' {
             mode = match mode {
-                Mode::Print => Mode::Pattern,
+                Mode::Print => {
+                    writer.write_out(&out);
+                    out.clear();
+                    Mode::Pattern
+                }
                 Mode::Pattern => Mode::Replacement,
                 Mode::Replacement => {
                     // Substitute using pattern and replacement
-                    pgm_str = pgm.collect::<String>();
+                    pgm.drain(..ind);
 
-                    while let Some(new_pgm) = repl_if_needed(&pgm_str, &patt, &repl) {
-                        pgm_str = new_pgm;
+                    while let Some((new_pgm, count)) = repl_if_needed(&pgm, &patt, &repl) {
+                        pgm = new_pgm;
+                        stats.substitutions = stats.substitutions.map(|s| s + count as u64);
                     }
                     // Reset everything
-                    pgm = pgm_str.chars();
+                    ind = 0;
                     patt.clear();
                     repl.clear();
 
@@ -45,26 +63,54 @@ pub fn interpret<T: LangWriter>(pgm_str: &str, _input: &str, _args: &str, writer
                 }
             };
         } else {
-            // Escape if backslash, so skip ahead to the next character
-            let chr = if chr == '\\' {
-                match pgm.next() {
-                    Some(c) => c,
+            // Escape if backslash, so skip ahead to the next byte
+            let byte = if byte == b'\\' {
+                match pgm.get(ind) {
+                    Some(&b) => {
+                        ind += 1;
+                        b
+                    }
                     None => continue,
                 }
             } else {
-                chr
+                byte
             };
             match mode {
-                Mode::Print => writer.write_out(&chr.to_string()),
-                Mode::Pattern => patt.push(chr),
-                Mode::Replacement => repl.push(chr),
+                Mode::Print => out.push(byte),
+                Mode::Pattern => patt.push(byte),
+                Mode::Replacement => repl.push(byte),
             }
         }
     }
+    writer.write_out(&out);
+    writer.write_stats(&stats);
 }
 
-fn repl_if_needed(input: &str, patt: &str, repl: &str) -> Option<String> {
-    input.contains(patt).then(|| input.replace(patt, repl))
+/// Replaces every occurrence of `patt`, like `str::replace` does for strings.
+fn repl_if_needed(input: &[u8], patt: &[u8], repl: &[u8]) -> Option<(Vec<u8>, usize)> {
+    let mut replaced = Vec::with_capacity(input.len());
+    let mut count = 0;
+    let mut ind = 0;
+    while ind + patt.len() <= input.len() {
+        if input[ind..].starts_with(patt) {
+            replaced.extend_from_slice(repl);
+            count += 1;
+            ind += patt.len();
+            if !patt.is_empty() {
+                continue;
+            }
+        }
+        if let Some(&byte) = input.get(ind) {
+            replaced.push(byte);
+        }
+        ind += 1;
+    }
+    replaced.extend_from_slice(input.get(ind..).unwrap_or_default());
+    if count > 0 {
+        Some((replaced, count))
+    } else {
+        None
+    }
 }
 
 #[derive(Debug)]
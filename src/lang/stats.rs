@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stats {
+    pub steps: u64,
+    pub peak_tape: Option<usize>,
+    pub max_loop_depth: Option<usize>,
+    pub substitutions: Option<u64>,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Steps: {}", self.steps)?;
+        if let Some(peak_tape) = self.peak_tape {
+            write!(f, "\nPeak tape size: {}", peak_tape)?;
+        }
+        if let Some(max_loop_depth) = self.max_loop_depth {
+            write!(f, "\nMax loop depth: {}", max_loop_depth)?;
+        }
+        if let Some(substitutions) = self.substitutions {
+            write!(f, "\nSubstitutions: {}", substitutions)?;
+        }
+        Ok(())
+    }
+}
@@ -1,96 +1,255 @@
 #![allow(clippy::wildcard_imports)]
 
+pub mod escape;
+pub mod import;
 pub mod lang;
+pub mod permalink;
+pub mod post;
 mod runner;
+pub mod runtime;
 mod threading;
 
-use data_encoding::BASE64URL_NOPAD as BASE64;
-use indoc::indoc;
+use permalink::{Permalink, TestCase};
+use post::{Post, Template};
+use runtime::controller::RunState;
+use runtime::limits::{OutputLimits, OverflowPolicy};
+use runtime::protocol::Job;
+use runtime::scheduler::Outcome;
 use seed::{prelude::*, *};
-use std::collections::hash_map::DefaultHasher;
-use std::hash::Hasher;
-use threading::prelude::OUT_LIMIT;
-use web_sys::window;
+use std::ops::Range;
+use std::rc::Rc;
+use web_sys::{window, HtmlTextAreaElement};
 
-fn init(mut url: Url, orders: &mut impl Orders<Msg>) -> Model {
+const POST_TEMPLATE_KEY: &str = "tib-post-template";
+const CUSTOM_TEMPLATE_KEY: &str = "tib-custom-template";
+const OUTPUT_LIMITS_KEY: &str = "tib-output-limits";
+
+fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
     orders.after_next_render(Msg::Rendered);
-    runner::init();
+    let workers = runner::init();
+    orders.perform_cmd(async move { Msg::WorkersReady(workers.await) });
     let languages_list = lang::get_lang_names();
-    let lang_part = url.next_hash_path_part();
-    let languages_shown = lang_part.is_none();
-    let lang = lang_part.map_or_else(|| languages_list[0].to_string(), b64_to_string);
+    let mut stderr = String::new();
+    let permalink = if url.hash_path().is_empty() {
+        None
+    } else {
+        Permalink::decode(url.hash_path())
+            .map_err(|err| stderr += &format!("Failed to load the permalink: {}", err))
+            .ok()
+    };
+    let languages_shown = permalink.is_none();
+    let Permalink {
+        lang,
+        lang_version,
+        code,
+        header,
+        footer,
+        tests,
+    } = permalink.unwrap_or_else(|| Permalink {
+        lang: languages_list[0].to_string(),
+        ..Permalink::default()
+    });
     log!(lang);
-    let code = url
-        .next_hash_path_part()
-        .map_or_else(|| "".to_string(), b64_to_string);
     log!(code);
-    let stdin = url
-        .next_hash_path_part()
-        .map_or_else(|| "".to_string(), b64_to_string);
-    log!(stdin);
-    let args = url
-        .next_hash_path_part()
-        .map_or_else(|| "".to_string(), b64_to_string);
-    log!(args);
+    log!(header);
+    log!(footer);
+    log!(tests);
+    let lang_version = pin_lang_version(&lang, &lang_version, &mut stderr);
+    let results = vec![None; tests.len()];
+    let code_escapes = escape::needs_escapes(&code);
+    let input_escapes = escape::needs_escapes(&tests[0].input);
     Model {
         spinner: 0,
-        thread_state: NotReady,
-        stdout: String::with_capacity(OUT_LIMIT),
-        stderr: String::with_capacity(OUT_LIMIT + 100),
+        runner: runner::Runner::default(),
+        stdout: String::new(),
+        stderr,
         lang,
-        code,
-        stdin,
-        args,
+        lang_version,
+        code: escape::to_text(&code, code_escapes),
+        code_escapes,
+        header,
+        footer,
+        input: escape::to_text(&tests[0].input, input_escapes),
+        input_escapes,
+        tests,
+        current_test: 0,
+        results,
+        batch: vec![],
+        batch_code: 0..0,
         languages_shown,
         languages_list,
         url,
         dragging: false,
         code_selection: String::default(),
+        post_template: LocalStorage::get::<_, String>(POST_TEMPLATE_KEY)
+            .ok()
+            .and_then(|name| Template::from_name(&name))
+            .unwrap_or(Template::Default),
+        custom_template: LocalStorage::get(CUSTOM_TEMPLATE_KEY)
+            .unwrap_or_else(|_| Template::Default.text().unwrap_or_default().to_string()),
+        output_limits: LocalStorage::get(OUTPUT_LIMITS_KEY).unwrap_or_default(),
     }
 }
 
-fn b64_to_string(s: &str) -> String {
-    match BASE64.decode(s[1..].as_bytes()) {
-        Ok(vec) => String::from_utf8_lossy(&vec).to_string(),
-        Err(_) => "<Failed to decode>".to_string(),
+/// A setting of the output limits, as edited in the UI.
+#[derive(Clone, Copy)]
+enum LimitSetting {
+    Stdout,
+    Stderr,
+    Policy,
+}
+
+#[derive(Clone, Copy)]
+enum Verdict {
+    Pass,
+    Fail,
+    Finished,
+    // Finished, but with output dropped by the limits
+    Truncated,
+    Error,
+}
+
+impl Verdict {
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Pass => "pass",
+            Self::Fail => "FAIL",
+            Self::Finished => "finished",
+            Self::Truncated => "truncated",
+            Self::Error => "ERROR",
+        }
     }
 }
 
-#[derive(PartialEq, Eq)]
-enum ThreadState {
-    NotReady,
-    Ready,
-    Running,
+/// The outcome of a test case's last run, kept so that switching cases shows their output.
+#[derive(Clone)]
+struct TestResult {
+    stdout: String,
+    stderr: String,
+    verdict: Verdict,
 }
-use ThreadState::{NotReady, Ready, Running};
 
 struct Model {
     spinner: usize,
-    thread_state: ThreadState,
+    runner: runner::Runner,
     stdout: String,
     stderr: String,
     lang: String,
+    // An older version of the language to run, as recorded in the permalink
+    lang_version: Option<String>,
+    // The code and the current test's stdin as shown in their textareas,
+    // with byte escapes if the corresponding flag is set
     code: String,
-    stdin: String,
-    args: String,
+    code_escapes: bool,
+    header: String,
+    footer: String,
+    input: String,
+    input_escapes: bool,
+    tests: Vec<TestCase>,
+    current_test: usize,
+    results: Vec<Option<TestResult>>,
+    // The test cases run by the current or last run, by job
+    batch: Vec<usize>,
+    // Where the code is in the full code of the current or last run, to show positions in it
+    batch_code: Range<usize>,
     languages_shown: bool,
     languages_list: &'static Vec<&'static str>,
     url: Url,
     dragging: bool,
     code_selection: String,
+    post_template: Template,
+    custom_template: String,
+    // The limits for the next run
+    output_limits: OutputLimits,
+}
+
+impl Model {
+    /// The job of the current or last run that runs the test case on display, if any.
+    fn current_job(&self) -> Option<usize> {
+        self.batch.iter().position(|&i| i == self.current_test)
+    }
+
+    fn permalink(&self) -> Permalink {
+        let lang_version = self
+            .lang_version
+            .as_deref()
+            .or_else(|| lang::get_version(&self.lang))
+            .unwrap_or_default();
+        Permalink {
+            lang: self.lang.clone(),
+            lang_version: lang_version.to_string(),
+            code: escape::from_text(&self.code, self.code_escapes),
+            header: self.header.clone(),
+            footer: self.footer.clone(),
+            tests: self.tests.clone(),
+        }
+    }
+
+    fn test_mut(&mut self) -> &mut TestCase {
+        self.results[self.current_test] = None;
+        &mut self.tests[self.current_test]
+    }
+
+    /// Escapes can only be turned off if the code is valid UTF-8.
+    fn toggle_code_escapes(&mut self) {
+        let code = escape::from_text(&self.code, self.code_escapes);
+        self.code_escapes = !self.code_escapes || escape::needs_escapes(&code);
+        self.code = escape::to_text(&code, self.code_escapes);
+    }
+
+    /// Limits are edited in KiB; values that don't parse are ignored.
+    fn set_limit(&mut self, setting: LimitSetting, value: &str) {
+        let kib = value.trim().parse::<usize>().ok().filter(|&kib| kib > 0);
+        match setting {
+            LimitSetting::Stdout => {
+                self.output_limits.stdout = kib.map_or(self.output_limits.stdout, |kib| kib * 1024);
+            }
+            LimitSetting::Stderr => {
+                self.output_limits.stderr = kib.map_or(self.output_limits.stderr, |kib| kib * 1024);
+            }
+            LimitSetting::Policy => {
+                if let Some(policy) = OverflowPolicy::from_name(value) {
+                    self.output_limits.policy = policy;
+                }
+            }
+        }
+        let _res = LocalStorage::insert(OUTPUT_LIMITS_KEY, &self.output_limits);
+    }
+
+    /// Shows the current test's stdin, turning escapes on if it is not valid UTF-8.
+    fn show_input(&mut self) {
+        let input = &self.tests[self.current_test].input;
+        self.input_escapes = self.input_escapes || escape::needs_escapes(input);
+        self.input = escape::to_text(input, self.input_escapes);
+    }
 }
 
 enum Msg {
     Rendered(RenderInfo),
+    WorkersReady(Rc<threading::WorkerPool>),
+    WorkerTaken(runner::Started),
     Stop,
     Run,
+    RunAll,
     LangSet(String),
     CodeUpdate(String),
+    CodeEscapesToggle,
     StdinUpdate(String),
+    StdinEscapesToggle,
     ArgsUpdate(String),
+    ExpectedUpdate(String),
+    TestSelect(usize),
+    TestAdd,
+    TestRemove,
+    HeaderUpdate(String),
+    FooterUpdate(String),
     LangListToggle,
     Linkify,
+    Import,
     Postify,
+    TemplateSet(String),
+    CustomTemplateUpdate(String),
+    LimitSet(LimitSetting, String),
     CodeSelect(bool),
 }
 
@@ -98,38 +257,74 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
     match msg {
         Msg::Rendered(_) => {
             model.spinner += 1;
-            update_state(model);
+            update_state(model, orders);
             orders.after_next_render(Msg::Rendered);
         }
+        Msg::WorkersReady(pool) => model.runner.set_pool(pool),
+        Msg::WorkerTaken(started) => worker_taken(model, orders, started),
         Msg::Run => {
             log!("Run clicked");
-            runner::reset_all_flags();
-            model.thread_state = Running;
-            model.stdout.clear();
-            model.stderr.clear();
-            runner::run(&model.lang, &model.code, &model.stdin, &model.args);
+            run_tests(model, orders, vec![model.current_test]);
         }
-        Msg::Stop => {
-            log!("Stop clicked");
-            model.thread_state = Ready;
-            model.stderr += &format!("\n\nElapsed time: {:.6} sec", runner::get_elapsed_time());
-            model.stderr += "\naborted";
-            runner::reset();
+        Msg::RunAll => {
+            log!("Run all clicked");
+            model.results = vec![None; model.tests.len()];
+            run_tests(model, orders, (0..model.tests.len()).collect());
         }
+        Msg::Stop => stop(model),
         Msg::LangSet(s) => {
             log!("Language set to", &s);
             model.lang = s;
+            model.lang_version = None;
             log!("current lang is", &model.lang);
         }
         Msg::CodeUpdate(s) => model.code = s,
-        Msg::StdinUpdate(s) => model.stdin = s,
-        Msg::ArgsUpdate(s) => model.args = s,
+        Msg::CodeEscapesToggle => model.toggle_code_escapes(),
+        Msg::StdinUpdate(s) => {
+            model.test_mut().input = escape::from_text(&s, model.input_escapes);
+            model.input = s;
+        }
+        Msg::StdinEscapesToggle => {
+            model.input_escapes = !model.input_escapes;
+            model.show_input();
+        }
+        Msg::ArgsUpdate(s) => model.test_mut().args = s,
+        Msg::ExpectedUpdate(s) => model.test_mut().expected = Some(s).filter(|s| !s.is_empty()),
+        Msg::TestSelect(i) => {
+            model.current_test = i;
+            model.show_input();
+            if let Some(result) = &model.results[i] {
+                model.stdout = result.stdout.clone();
+                model.stderr = result.stderr.clone();
+            } else {
+                model.stdout.clear();
+                model.stderr.clear();
+            }
+        }
+        Msg::TestAdd => {
+            model.tests.push(TestCase::default());
+            model.results.push(None);
+            model.current_test = model.tests.len() - 1;
+            model.show_input();
+            model.stdout.clear();
+            model.stderr.clear();
+        }
+        Msg::TestRemove => {
+            if model.tests.len() > 1 {
+                model.tests.remove(model.current_test);
+                model.results.remove(model.current_test);
+                model.current_test = model.current_test.min(model.tests.len() - 1);
+                model.show_input();
+            }
+        }
+        Msg::HeaderUpdate(s) => model.header = s,
+        Msg::FooterUpdate(s) => model.footer = s,
         Msg::CodeSelect(is_start) => {
             if is_start {
                 model.dragging = true;
             } else if model.dragging {
                 model.dragging = false;
-                model.code_selection = get_selection().unwrap_or_default();
+                model.code_selection = get_selection(&model.code).unwrap_or_default();
             } else {
                 model.code_selection = String::default();
             }
@@ -138,158 +333,281 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
         Msg::LangListToggle => {
             model.languages_shown = !model.languages_shown;
         }
-        Msg::Linkify => {
-            model.url = update_url(
-                model.url.clone(),
-                &model.lang,
-                &model.code,
-                &model.stdin,
-                &model.args,
-            );
-            //model.running_text.clear();
-            model.stdout.clear();
-            model.stdout += "https://try-in-browser.netlify.app/#";
-            model.stdout += model.url.hash().unwrap_or(&"".to_string());
-            model.stderr.clear();
+        Msg::Linkify => linkify(model),
+        Msg::Import => import_link(model),
+        Msg::TemplateSet(name) => {
+            if let Some(template) = Template::from_name(&name) {
+                model.post_template = template;
+                let _res = LocalStorage::insert(POST_TEMPLATE_KEY, &name);
+            }
         }
-        Msg::Postify => {
-            model.url = update_url(
-                model.url.clone(),
-                &model.lang,
-                &model.code,
-                &model.stdin,
-                &model.args,
-            );
-            //model.running_text.clear();
-            model.stdout.clear();
-            model.stdout += &model.url.to_string();
-            let homepage = lang::get_homepage(&model.lang).unwrap_or("");
-            model.stdout = format_post(
-                &model.lang,
-                &model.code,
-                &model.stdin,
-                &model.args,
-                &model.code_selection,
-                homepage,
-                &model.url,
-            );
-            model.stderr.clear();
+        Msg::CustomTemplateUpdate(s) => {
+            let _res = LocalStorage::insert(CUSTOM_TEMPLATE_KEY, &s);
+            model.custom_template = s;
         }
+        Msg::Postify => postify(model),
+        Msg::LimitSet(setting, value) => model.set_limit(setting, &value),
     }
 }
 
-fn update_state(model: &mut Model) {
-    if model.thread_state == NotReady && runner::poll_mt_init() {
-        model.thread_state = Ready;
-    }
-    if model.thread_state == Running {
-        let finished = runner::get_th_finished();
-        let (out, err) = runner::take_result_from_thread();
-        let stdout_overflown = model.stdout.len() + out.len() > OUT_LIMIT;
-        let stderr_overflown = model.stderr.len() + err.len() > OUT_LIMIT;
-        let overflown = stdout_overflown || stderr_overflown;
-        if stdout_overflown {
-            model
-                .stdout
-                .push_str(&out[..OUT_LIMIT - model.stdout.len()]);
-        } else {
-            model.stdout.push_str(&out);
-        }
-        if stderr_overflown {
-            model
-                .stderr
-                .push_str(&err[..OUT_LIMIT - model.stderr.len()]);
-        } else {
-            model.stderr.push_str(&err);
+fn stop(model: &mut Model) {
+    log!("Stop clicked");
+    let cancelled = model.runner.stop();
+    record_results(model, &cancelled);
+}
+
+fn linkify(model: &mut Model) {
+    let permalink = model.permalink();
+    model.url = update_url(model.url.clone(), &permalink);
+    //model.running_text.clear();
+    model.stdout.clear();
+    model.stdout += &permalink.to_link_at(&site_url());
+    model.stderr.clear();
+    model.stderr += &format!("Program fingerprint: {:016x}", permalink.fingerprint());
+}
+
+fn postify(model: &mut Model) {
+    let permalink = model.permalink();
+    model.url = update_url(model.url.clone(), &permalink);
+    //model.running_text.clear();
+    model.stdout.clear();
+    model.stdout += &model.url.to_string();
+    let homepage = lang::get_homepage(&model.lang).unwrap_or("");
+    let template = model.post_template.text().unwrap_or(&model.custom_template);
+    let selection = escape::from_text(&model.code_selection, model.code_escapes);
+    model.stdout = format_post(template, &permalink, &selection, homepage, &site_url());
+    model.stderr.clear();
+}
+
+/// Asks for a TIO or ATO link and replaces the whole session with its contents.
+fn import_link(model: &mut Model) {
+    let url = window()
+        .and_then(|w| {
+            w.prompt_with_message("Paste a Try It Online or Attempt This Online link")
+                .ok()
+        })
+        .flatten();
+    if let Some(url) = url.filter(|url| !url.trim().is_empty()) {
+        model.stdout.clear();
+        model.stderr.clear();
+        match import::import_url(&url) {
+            Ok(permalink) => {
+                model.url = update_url(model.url.clone(), &permalink);
+                model.lang_version =
+                    pin_lang_version(&permalink.lang, &permalink.lang_version, &mut model.stderr);
+                model.lang = permalink.lang;
+                model.code_escapes = escape::needs_escapes(&permalink.code);
+                model.code = escape::to_text(&permalink.code, model.code_escapes);
+                model.header = permalink.header;
+                model.footer = permalink.footer;
+                model.results = vec![None; permalink.tests.len()];
+                model.tests = permalink.tests;
+                model.current_test = 0;
+                model.input_escapes = false;
+                model.show_input();
+            }
+            Err(err) => model.stderr += &format!("Failed to import the link: {}", err),
         }
-        if overflown {
-            runner::reset();
+    }
+}
+
+/// Decides which version of `lang` a link made with `version` should run with,
+/// warning in `stderr` if it is not the current one.
+fn pin_lang_version(lang: &str, version: &str, stderr: &mut String) -> Option<String> {
+    let current = lang::get_version(lang)?;
+    if version.is_empty() || version == current {
+        None
+    } else if lang::has_version(lang, version) {
+        *stderr += &format!(
+            "This link was made with {} {}, which is used to run it (the current version is {}).",
+            lang, version, current
+        );
+        Some(version.to_string())
+    } else {
+        *stderr += &format!(
+            "Warning: this link was made with {} {}, but only version {} is available, so the program may behave differently.",
+            lang, version, current
+        );
+        None
+    }
+}
+
+/// Picks up the output and outcomes of the running jobs.
+fn update_state(model: &mut Model, orders: &mut impl Orders<Msg>) {
+    if model.runner.state() != RunState::Running {
+        return;
+    }
+    let ended = model.runner.poll();
+    record_results(model, &ended);
+    // Stream the output of the test case on display
+    let runner = &model.runner;
+    if let Some(result) = model.current_job().and_then(|job| runner.result(job)) {
+        if result.outcome.is_none() {
+            model.stdout = output_text(&result.stdout);
+            model.stderr = output_text(&result.stderr);
         }
-        let crashed = runner::get_th_crashed() || overflown;
-        if crashed || finished {
-            model.thread_state = Ready;
+    }
+    request_workers(model, orders);
+}
+
+/// Asks for the workers the run can use, including replacements for crashed ones.
+fn request_workers(model: &mut Model, orders: &mut impl Orders<Msg>) {
+    for worker in model.runner.request_workers() {
+        orders.perform_cmd(async move { Msg::WorkerTaken(worker.await) });
+    }
+}
+
+fn worker_taken(model: &mut Model, orders: &mut impl Orders<Msg>, started: runner::Started) {
+    let ended = model.runner.started(started);
+    record_results(model, &ended);
+    request_workers(model, orders);
+}
+
+/// Runs the given test cases at once, showing the first one.
+fn run_tests(model: &mut Model, orders: &mut impl Orders<Msg>, tests: Vec<usize>) {
+    let permalink = model.permalink();
+    let code = permalink.full_code();
+    let jobs = tests
+        .iter()
+        .map(|&i| Job {
+            lang: model.lang.clone(),
+            version: model.lang_version.clone(),
+            code: code.clone(),
+            input: model.tests[i].input.clone(),
+            args: model.tests[i].args.clone(),
+        })
+        .collect();
+    if model.runner.start(jobs, model.output_limits) {
+        model.current_test = tests[0];
+        model.batch = tests;
+        model.batch_code = permalink.code_range();
+        model.stdout.clear();
+        model.stderr.clear();
+        request_workers(model, orders);
+    }
+}
+
+/// Output as shown in the output fields. Like code, it is shown with escapes if it is not
+/// valid UTF-8.
+fn output_text(bytes: &[u8]) -> String {
+    escape::to_text(bytes, escape::needs_escapes(bytes))
+}
+
+/// Records how the given jobs ended, with their output.
+fn record_results(model: &mut Model, jobs: &[usize]) {
+    let runner = &model.runner;
+    let results = jobs
+        .iter()
+        .filter_map(|&job| Some((job, runner.result(job)?)));
+    for (job, result) in results {
+        let mut stderr = output_text(&result.stderr);
+        stderr += &format!("\n\nElapsed time: {:.6} sec", runner.get_elapsed_time(job));
+        if let Some(stats) = &result.stats {
+            stderr += &format!("\n{}", stats);
         }
-        if crashed || finished {
-            model.stderr += &format!("\n\nElapsed time: {:.6} sec", runner::get_elapsed_time());
+        stderr += match result.outcome {
+            Some(Outcome::Finished) => "\nfinished",
+            Some(Outcome::Failed(_)) => "\nerror",
+            Some(Outcome::OutputLimit) => "\noutput limit exceeded",
+            Some(Outcome::Cancelled) => "\naborted",
+            Some(Outcome::Error(_)) | None => "\ninterpreter crashed",
+        };
+        let notes = [
+            result.limiters.0.note("stdout"),
+            result.limiters.1.note("stderr"),
+        ];
+        for note in notes.iter().flatten() {
+            stderr += &format!("\n{}", note);
         }
-        if crashed {
-            model.stderr += if overflown {
-                "\noutput limit exceeded"
-            } else {
-                "\ninterpreter crashed"
-            };
-        } else if finished {
-            model.stderr += "\nfinished";
+        let test = model.batch[job];
+        let verdict = if result.outcome != Some(Outcome::Finished) {
+            Verdict::Error
+        } else if result.is_truncated() {
+            Verdict::Truncated
+        } else {
+            match model.tests[test].check(&result.stdout) {
+                Some(true) => Verdict::Pass,
+                Some(false) => Verdict::Fail,
+                None => Verdict::Finished,
+            }
+        };
+        let stdout = output_text(&result.stdout);
+        if test == model.current_test {
+            model.stdout.clone_from(&stdout);
+            model.stderr.clone_from(&stderr);
         }
+        model.results[test] = Some(TestResult {
+            stdout,
+            stderr,
+            verdict,
+        });
     }
 }
 
+/// The header and footer are left out of the post: only the code counts towards the score.
+/// Posts are text, so bytes that are not valid UTF-8 show up as U+FFFD.
 fn format_post(
-    lang: &str,
-    code: &str,
-    input: &str,
-    args: &str,
-    selection: &str,
+    template: &str,
+    permalink: &Permalink,
+    selection: &[u8],
     lang_link: &str,
-    url: &Url,
+    site_url: &str,
 ) -> String {
-    let mut hasher = DefaultHasher::new();
-    hasher.write(lang.as_bytes());
-    hasher.write_u8(0);
-    hasher.write(code.as_bytes());
-    hasher.write_u8(0);
-    hasher.write(input.as_bytes());
-    hasher.write_u8(0);
-    hasher.write(args.as_bytes());
-    let hash = hasher.finish();
-    let display_code = if selection.is_empty() {
-        code
+    let code = if selection.is_empty() {
+        &permalink.code
     } else {
         selection
     };
-    format!(
-        indoc!(
-            r#"
-            # [{0}][tib-{0}], {1} byte{2}
-
-            ```
-            {3}
-            ```
-
-            [Try in browser!][tib-{4:016x}]
+    let display_code = String::from_utf8_lossy(code);
+    let link = permalink.to_link_at(site_url);
+    post::render(
+        template,
+        &Post {
+            lang: &permalink.lang,
+            homepage: lang_link,
+            code: &display_code,
+            bytes: code.len(),
+            link: &link,
+            hash: permalink.fingerprint(),
+        },
+    )
+}
 
-            [tib-{0}]: {5}
-            [tib-{4:016x}]: https://try-in-browser.netlify.app/#{6}
-            "#
-        ),
-        lang,
-        display_code.len(),
-        if display_code.len() == 1 { "" } else { "s" },
-        display_code,
-        hash,
-        lang_link,
-        url.hash().unwrap_or(&"".to_string())
+/// The address links should point to: the build-time `TIB_SITE_URL` if set,
+/// otherwise the page the app is currently served from.
+fn site_url() -> String {
+    option_env!("TIB_SITE_URL").map_or_else(
+        || {
+            window()
+                .and_then(|w| w.location().href().ok())
+                .unwrap_or_else(|| permalink::SITE_URL.to_string())
+        },
+        ToString::to_string,
     )
 }
 
-fn update_url(url: Url, lang: &str, code: &str, input: &str, args: &str) -> Url {
-    let lang = "@".to_string() + &BASE64.encode(lang.as_bytes());
-    let code = "@".to_string() + &BASE64.encode(code.as_bytes());
-    let input = "@".to_string() + &BASE64.encode(input.as_bytes());
-    let args = "@".to_string() + &BASE64.encode(args.as_bytes());
-    let url = url.set_hash_path(&[lang, code, input, args]);
+fn update_url(url: Url, permalink: &Permalink) -> Url {
+    let url = url.set_hash_path(&permalink.encode());
     url.go_and_replace();
     url
 }
 
-fn get_selection() -> Option<String> {
-    let window = window();
-    let selection = window.and_then(|w| w.get_selection().ok()).flatten();
-    let s: Option<String> = selection.map(|s| s.to_string().into());
-    s
+/// Reads the selection from the code textarea itself rather than the page selection,
+/// so that the selected text is exactly the corresponding bytes of the code.
+fn get_selection(code: &str) -> Option<String> {
+    let textarea = window()?
+        .document()?
+        .get_element_by_id("code")?
+        .dyn_into::<HtmlTextAreaElement>()
+        .ok()?;
+    let start = textarea.selection_start().ok()??;
+    let end = textarea.selection_end().ok()??;
+    Some(post::selected_code(code, start as usize, end as usize).to_string())
 }
 
 fn view(model: &Model) -> Node<Msg> {
+    let ready = model.runner.is_ready();
+    let busy = model.runner.state() != RunState::Idle;
     div![
         IF!(cfg!(feature="ui_debug") => div![
             "UI health: ", ".".repeat(model.spinner / 10 % 10),
@@ -317,15 +635,23 @@ fn view(model: &Model) -> Node<Msg> {
                         C![
                             IF!(&model.lang == s => "active"),
                             IF!(&model.lang != s => "inactive"),
-                            IF!(model.thread_state == Running => "disabled")
+                            IF!(busy => "disabled")
                         ],
                         s,
-                        IF!(model.thread_state != Running => ev(Ev::Click, move |_| Msg::LangSet(s_clone)))
+                        IF!(!busy => ev(Ev::Click, move |_| Msg::LangSet(s_clone)))
                     ]
                 })
         ],
         br![],
+        b!["Header (not counted in Postify)"],
+        textarea![
+            id!("header"),
+            attrs! {At::SpellCheck => false, At::Rows => rows(&model.header, 1), At::Cols => COLS, At::Value => model.header},
+            input_ev(Ev::Input, Msg::HeaderUpdate)
+        ],
+        br![],
         b!["Code"],
+        escapes_checkbox(model.code_escapes, || Msg::CodeEscapesToggle),
         textarea![
             id!("code"),
             attrs! {At::SpellCheck => false, At::Rows => rows(&model.code, 4), At::Cols => COLS, At::Value => model.code},
@@ -333,62 +659,271 @@ fn view(model: &Model) -> Node<Msg> {
             ev(Ev::MouseDown, |_| Msg::CodeSelect(true))
         ],
         br![],
-        b!["Stdin"],
+        b!["Footer (not counted in Postify)"],
         textarea![
-            id!("stdin"),
-            attrs! {At::SpellCheck => false, At::Rows => rows(&model.stdin, 4), At::Cols => COLS, At::Value => model.stdin},
-            input_ev(Ev::Input, Msg::StdinUpdate)
+            id!("footer"),
+            attrs! {At::SpellCheck => false, At::Rows => rows(&model.footer, 1), At::Cols => COLS, At::Value => model.footer},
+            input_ev(Ev::Input, Msg::FooterUpdate)
         ],
         br![],
-        b!["Arguments (enter -h and press Run for usage)"],
+        view_tests(model),
+        view_run(model, ready),
+        br![],
+        view_limits(&model.output_limits, busy),
+        br![],
+        view_post(model, ready),
+        br![],
+        br![],
+        b!["Output"],
         textarea![
-            id!("args"),
-            attrs! {At::SpellCheck => false, At::Rows => rows(&model.args, 1), At::Cols => COLS, At::Value => model.args},
-            input_ev(Ev::Input, Msg::ArgsUpdate)
+            id!("stdout"),
+            attrs! {At::Rows => rows(&model.stdout, 1), At::Cols => COLS, At::Value => model.stdout},
         ],
         br![],
+        b!["Error"],
+        textarea![
+            id!("stderr"),
+            attrs! {At::Rows => rows(&model.stderr, 1), At::Cols => COLS, At::Value => model.stderr},
+        ],
+        ev(Ev::MouseUp, |_| Msg::CodeSelect(false)),
+    ]
+}
+
+/// The buttons that start and stop runs, with the progress of the test case on display.
+fn view_run(model: &Model, ready: bool) -> Vec<Node<Msg>> {
+    let stoppable = matches!(model.runner.state(), RunState::Starting | RunState::Running);
+    vec![
         button![
             id!("run"),
-            attrs! { At::Disabled => (model.thread_state != Ready).as_at_value() },
+            attrs! { At::Disabled => (!ready).as_at_value() },
             "Run",
             ev(Ev::Click, |_| Msg::Run)
         ],
+        button![
+            id!("run-all"),
+            attrs! { At::Disabled => (!ready).as_at_value() },
+            "Run all",
+            ev(Ev::Click, |_| Msg::RunAll)
+        ],
         button![
             id!("stop"),
-            attrs! { At::Disabled => (model.thread_state != Running).as_at_value() },
+            attrs! { At::Disabled => (!stoppable).as_at_value() },
             "Stop",
             ev(Ev::Click, |_| Msg::Stop)
         ],
-        br![],
+        view_progress(model),
+    ]
+}
+
+/// The buttons that share the program, with the template used by Postify.
+fn view_post(model: &Model, ready: bool) -> Vec<Node<Msg>> {
+    vec![
         button![
             id!("linkify"),
-            attrs! { At::Disabled => (model.thread_state != Ready).as_at_value() },
+            attrs! { At::Disabled => (!ready).as_at_value() },
             "Linkify",
             ev(Ev::Click, |_| Msg::Linkify)
         ],
+        button![
+            id!("import"),
+            attrs! { At::Disabled => (!ready).as_at_value() },
+            "Import",
+            ev(Ev::Click, |_| Msg::Import)
+        ],
         button![
             id!("postify"),
-            attrs! { At::Disabled => (model.thread_state != Ready).as_at_value() },
+            attrs! { At::Disabled => (!ready).as_at_value() },
             "Postify",
             ev(Ev::MouseDown, |_| Msg::Postify)
         ],
+        select![
+            id!("template"),
+            attrs! { At::Disabled => (!ready).as_at_value() },
+            Template::ALL.iter().map(|template| {
+                option![
+                    attrs! { At::Value => template.name() },
+                    IF!(*template == model.post_template => attrs! { At::Selected => AtValue::None }),
+                    template.name()
+                ]
+            }),
+            input_ev(Ev::Change, Msg::TemplateSet)
+        ],
+        IF!(model.post_template == Template::Custom => vec![
+            br![],
+            b![format!("Custom template (placeholders: {})", post::PLACEHOLDERS.join(" "))],
+            textarea![
+                id!("custom-template"),
+                attrs! {At::SpellCheck => false, At::Rows => rows(&model.custom_template, 4), At::Cols => COLS, At::Value => model.custom_template},
+                input_ev(Ev::Input, Msg::CustomTemplateUpdate)
+            ],
+        ]),
+    ]
+}
+
+fn view_tests(model: &Model) -> Vec<Node<Msg>> {
+    let busy = model.runner.state() != RunState::Idle;
+    let test = &model.tests[model.current_test];
+    let expected = test.expected.as_deref().unwrap_or("");
+    vec![
+        b!["Test cases"],
+        div![
+            id!("tests"),
+            model.tests.iter().enumerate().map(|(i, _)| {
+                let label = model.results[i].as_ref().map_or_else(
+                    || format!("#{}", i + 1),
+                    |result| format!("#{} {}", i + 1, result.verdict.label()),
+                );
+                button![
+                    C![IF!(i == model.current_test => "active")],
+                    attrs! { At::Disabled => busy.as_at_value() },
+                    label,
+                    ev(Ev::Click, move |_| Msg::TestSelect(i))
+                ]
+            }),
+            button![
+                id!("add-test"),
+                attrs! { At::Disabled => busy.as_at_value() },
+                "+",
+                ev(Ev::Click, |_| Msg::TestAdd)
+            ],
+            button![
+                id!("remove-test"),
+                attrs! { At::Disabled => (busy || model.tests.len() == 1).as_at_value() },
+                "-",
+                ev(Ev::Click, |_| Msg::TestRemove)
+            ],
+            test_summary(&model.results).map(|summary| div![summary]),
+        ],
+        b!["Stdin"],
+        escapes_checkbox(model.input_escapes, || Msg::StdinEscapesToggle),
+        textarea![
+            id!("stdin"),
+            attrs! {At::SpellCheck => false, At::Rows => rows(&model.input, 4), At::Cols => COLS, At::Value => model.input},
+            input_ev(Ev::Input, Msg::StdinUpdate)
+        ],
         br![],
-        br![],
-        b!["Output"],
+        b!["Arguments (enter -h and press Run for usage)"],
         textarea![
-            id!("stdout"),
-            attrs! {At::Rows => rows(&model.stdout, 1), At::Cols => COLS, At::Value => model.stdout},
+            id!("args"),
+            attrs! {At::SpellCheck => false, At::Rows => rows(&test.args, 1), At::Cols => COLS, At::Value => test.args},
+            input_ev(Ev::Input, Msg::ArgsUpdate)
         ],
         br![],
-        b!["Error"],
+        b!["Expected output (optional)"],
         textarea![
-            id!("stderr"),
-            attrs! {At::Rows => rows(&model.stderr, 1), At::Cols => COLS, At::Value => model.stderr},
+            id!("expected"),
+            attrs! {At::SpellCheck => false, At::Rows => rows(expected, 1), At::Cols => COLS, At::Value => expected},
+            input_ev(Ev::Input, Msg::ExpectedUpdate)
         ],
-        ev(Ev::MouseUp, |_| Msg::CodeSelect(false)),
+        br![],
     ]
 }
 
+/// How far the test case on display got while it runs, as last reported by its worker.
+fn view_progress(model: &Model) -> Node<Msg> {
+    let job = model
+        .current_job()
+        .filter(|_| model.runner.state() == RunState::Running);
+    let result = job.and_then(|job| Some((job, model.runner.result(job)?)));
+    if let Some((job, result)) = result.filter(|(_, result)| result.outcome.is_none()) {
+        let mut status = format!(
+            " Elapsed time: {:.1} sec",
+            model.runner.get_elapsed_time(job)
+        );
+        if let Some(progress) = &result.progress {
+            status += &format!(
+                ", steps: {}, output: {} bytes",
+                progress.steps,
+                progress.stdout + progress.stderr
+            );
+            // The position is hidden while the header or the footer runs
+            let code = &model.batch_code;
+            let ip = progress.ip.filter(|ip| code.contains(ip));
+            if let Some(ip) = ip {
+                status += &format!(", at {}", ip - code.start);
+            }
+        }
+        span![id!("progress"), status]
+    } else {
+        empty![]
+    }
+}
+
+/// The output limits for the next run, in KiB.
+fn view_limits(limits: &OutputLimits, busy: bool) -> Vec<Node<Msg>> {
+    let limit_input = |name: &str, limit: usize, setting: LimitSetting| {
+        label![
+            format!(" {} ", name),
+            input![
+                attrs! {
+                    At::Type => "number",
+                    At::Min => 1,
+                    At::Size => 6,
+                    At::Value => limit / 1024,
+                    At::Disabled => busy.as_at_value(),
+                },
+                input_ev(Ev::Change, move |value| Msg::LimitSet(setting, value))
+            ],
+        ]
+    };
+    vec![
+        b!["Output limit (KiB)"],
+        limit_input("stdout", limits.stdout, LimitSetting::Stdout),
+        limit_input("stderr", limits.stderr, LimitSetting::Stderr),
+        label![
+            " on overflow ",
+            select![
+                attrs! { At::Disabled => busy.as_at_value() },
+                OverflowPolicy::ALL.iter().map(|policy| {
+                    option![
+                        attrs! { At::Value => policy.name() },
+                        IF!(*policy == limits.policy => attrs! { At::Selected => AtValue::None }),
+                        policy.name()
+                    ]
+                }),
+                input_ev(Ev::Change, |value| Msg::LimitSet(
+                    LimitSetting::Policy,
+                    value
+                ))
+            ],
+        ],
+    ]
+}
+
+/// Toggles showing a field's bytes with escapes, which is needed to edit invalid UTF-8.
+fn escapes_checkbox(checked: bool, msg: fn() -> Msg) -> Node<Msg> {
+    label![
+        input![
+            attrs! { At::Type => "checkbox", At::Checked => checked.as_at_value() },
+            ev(Ev::Change, move |_| msg())
+        ],
+        "Byte escapes (\\xhh, \\\\)"
+    ]
+}
+
+/// Summarizes the results of "Run all", once there is more than one test case to speak of.
+fn test_summary(results: &[Option<TestResult>]) -> Option<String> {
+    if results.len() < 2 || results.iter().all(Option::is_none) {
+        return None;
+    }
+    let count = |f: fn(Verdict) -> bool| {
+        results
+            .iter()
+            .flatten()
+            .filter(|result| f(result.verdict))
+            .count()
+    };
+    Some(format!(
+        "{} test cases: {} passed, {} failed, {} errors, {} truncated, {} without expected output",
+        results.len(),
+        count(|v| matches!(v, Verdict::Pass)),
+        count(|v| matches!(v, Verdict::Fail)),
+        count(|v| matches!(v, Verdict::Error)),
+        count(|v| matches!(v, Verdict::Truncated)),
+        count(|v| matches!(v, Verdict::Finished)),
+    ))
+}
+
 const COLS: usize = 80;
 fn rows(s: &str, min_rows: usize) -> usize {
     s.split('\n')
@@ -0,0 +1,355 @@
+#![allow(clippy::cast_possible_truncation)]
+#![allow(clippy::must_use_candidate)]
+use data_encoding::BASE64URL_NOPAD as BASE64;
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+use std::fmt;
+use std::ops::Range;
+
+/// Where links made outside the browser point to.
+/// Set `TIB_SITE_URL` at build time to override it, e.g. for a mirror.
+pub const SITE_URL: &str = match option_env!("TIB_SITE_URL") {
+    Some(url) => url,
+    None => "https://try-in-browser.netlify.app/",
+};
+
+/// First hash path part of links in the current format.
+/// Links without a marker are the legacy format, where every field is a separate `@`-prefixed part.
+pub const VERSION_MARKER: &str = "~2";
+
+/// Marker of links made before language versions were recorded.
+const VERSION_MARKER_V1: &str = "~1";
+
+const COMPRESSION_LEVEL: u8 = 9;
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+const MAX_DECODED_SIZE: usize = 1 << 24;
+
+/// Prefix of an encoded expected output; an empty field means there is none.
+const EXPECTED_MARKER: char = '=';
+
+/// A program with one or more test cases. `tests` is never empty.
+/// The code and the inputs are raw bytes, which need not be valid UTF-8.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Permalink {
+    pub lang: String,
+    /// Version of the language the link was made with; empty if unknown
+    pub lang_version: String,
+    pub code: Vec<u8>,
+    pub header: String,
+    pub footer: String,
+    pub tests: Vec<TestCase>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TestCase {
+    pub input: Vec<u8>,
+    pub args: String,
+    pub expected: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    UnknownVersion(String),
+    InvalidBase64,
+    InvalidCompression,
+    ChecksumMismatch,
+    InvalidUtf8,
+    Truncated,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownVersion(marker) => write!(f, "unknown link version `{}`", marker),
+            Self::InvalidBase64 => write!(f, "the link contains invalid characters"),
+            Self::InvalidCompression => write!(f, "the link data is corrupted"),
+            Self::ChecksumMismatch => write!(f, "the link checksum does not match"),
+            Self::InvalidUtf8 => write!(f, "the link contains invalid UTF-8"),
+            Self::Truncated => write!(f, "the link is truncated"),
+        }
+    }
+}
+
+impl Default for Permalink {
+    fn default() -> Self {
+        Self {
+            lang: String::new(),
+            lang_version: String::new(),
+            code: vec![],
+            header: String::new(),
+            footer: String::new(),
+            tests: vec![TestCase::default()],
+        }
+    }
+}
+
+impl TestCase {
+    pub fn new(input: impl AsRef<[u8]>, args: &str) -> Self {
+        Self {
+            input: input.as_ref().to_vec(),
+            args: args.to_string(),
+            expected: None,
+        }
+    }
+
+    /// Checks `stdout` against the expected output, ignoring trailing newlines.
+    /// Returns `None` if there is no expected output.
+    pub fn check(&self, stdout: &[u8]) -> Option<bool> {
+        self.expected
+            .as_ref()
+            .map(|expected| trim_newlines(stdout) == trim_newlines(expected.as_bytes()))
+    }
+}
+
+fn trim_newlines(bytes: &[u8]) -> &[u8] {
+    let end = bytes
+        .iter()
+        .rposition(|&byte| byte != b'\n')
+        .map_or(0, |i| i + 1);
+    &bytes[..end]
+}
+
+impl Permalink {
+    /// Creates a permalink with a single test case.
+    pub fn new(lang: &str, code: impl AsRef<[u8]>, input: impl AsRef<[u8]>, args: &str) -> Self {
+        Self {
+            lang: lang.to_string(),
+            code: code.as_ref().to_vec(),
+            tests: vec![TestCase::new(input, args)],
+            ..Self::default()
+        }
+    }
+
+    /// The code that is actually run: header, code and footer on separate lines.
+    /// Empty sections are left out, so a program without header or footer runs unchanged.
+    pub fn full_code(&self) -> Vec<u8> {
+        [self.header.as_bytes(), &self.code, self.footer.as_bytes()]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join(&b'\n')
+    }
+
+    /// Where the code is in `full_code`.
+    pub fn code_range(&self) -> Range<usize> {
+        let start = if self.header.is_empty() {
+            0
+        } else {
+            self.header.len() + 1
+        };
+        start..start + self.code.len()
+    }
+
+    /// Encodes the fields as `[VERSION_MARKER, data]`, where `data` is the base64url encoding of
+    /// the deflate-compressed fields followed by the big-endian CRC-32 of the uncompressed fields.
+    /// The fields are the language, its version, the code, the header and the footer,
+    /// followed by the input, args and expected output of every test case.
+    pub fn encode(&self) -> [String; 2] {
+        let payload = payload(&self.fields());
+        let mut data = compress_to_vec(&payload, COMPRESSION_LEVEL);
+        data.extend_from_slice(&crc32fast::hash(&payload).to_be_bytes());
+        [VERSION_MARKER.to_string(), BASE64.encode(&data)]
+    }
+
+    /// A stable 64-bit FNV-1a hash of the program, so that the same submission gets the same
+    /// fingerprint in the UI, `runtib` and across releases, whatever its test cases.
+    ///
+    /// The hashed bytes are the language name and then the full code, each prefixed with its
+    /// length as in the link payload. This format must never change.
+    pub fn fingerprint(&self) -> u64 {
+        payload(&[self.lang.clone().into_bytes(), self.full_code()])
+            .iter()
+            .fold(FNV_OFFSET_BASIS, |hash, &byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+            })
+    }
+
+    fn fields(&self) -> Vec<Vec<u8>> {
+        let mut fields = vec![
+            self.lang.clone().into_bytes(),
+            self.lang_version.clone().into_bytes(),
+            self.code.clone(),
+            self.header.clone().into_bytes(),
+            self.footer.clone().into_bytes(),
+        ];
+        for test in &self.tests {
+            fields.push(test.input.clone());
+            fields.push(test.args.clone().into_bytes());
+            fields.push(encode_expected(test.expected.as_deref()));
+        }
+        fields
+    }
+
+    /// Decodes a hash path produced by `encode`, by the `~1` format or by the legacy format.
+    /// Missing trailing fields are left empty, and unknown trailing fields are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the link is corrupted or has an unknown version marker.
+    pub fn decode<S: AsRef<str>>(hash_path: &[S]) -> Result<Self, DecodeError> {
+        let data = hash_path.get(1).map_or("", AsRef::as_ref);
+        match hash_path.first().map(AsRef::as_ref) {
+            Some(VERSION_MARKER) => Self::from_fields(decode_fields(data)?),
+            Some(VERSION_MARKER_V1) => Self::from_fields_v1(decode_fields(data)?),
+            Some(marker) if marker.starts_with('~') => {
+                Err(DecodeError::UnknownVersion(marker.to_string()))
+            }
+            _ => Self::from_fields_v1(
+                hash_path
+                    .iter()
+                    .map(|part| decode_legacy_field(part.as_ref()))
+                    .collect::<Result<_, _>>()?,
+            ),
+        }
+    }
+
+    fn from_fields(fields: Vec<Vec<u8>>) -> Result<Self, DecodeError> {
+        let mut fields = fields.into_iter();
+        let mut next = || fields.next().unwrap_or_default();
+        let (lang, lang_version, code, header, footer) = (next(), next(), next(), next(), next());
+        let mut tests = decode_tests(fields)?;
+        if tests.is_empty() {
+            tests.push(TestCase::default());
+        }
+        Ok(Self {
+            lang: text(lang)?,
+            lang_version: text(lang_version)?,
+            code,
+            header: text(header)?,
+            footer: text(footer)?,
+            tests,
+        })
+    }
+
+    fn from_fields_v1(fields: Vec<Vec<u8>>) -> Result<Self, DecodeError> {
+        let mut fields = fields.into_iter();
+        let mut next = || fields.next().unwrap_or_default();
+        let (lang, code, input, args, header, footer) =
+            (next(), next(), next(), next(), next(), next());
+        let first = TestCase {
+            input,
+            args: text(args)?,
+            expected: decode_expected(next())?,
+        };
+        let mut tests = vec![first];
+        tests.extend(decode_tests(fields)?);
+        Ok(Self {
+            lang: text(lang)?,
+            lang_version: String::new(),
+            code,
+            header: text(header)?,
+            footer: text(footer)?,
+            tests,
+        })
+    }
+
+    pub fn to_link(&self) -> String {
+        self.to_link_at(SITE_URL)
+    }
+
+    /// Builds a link to the deployment at `site_url`. Any fragment already in it is replaced.
+    pub fn to_link_at(&self, site_url: &str) -> String {
+        let site_url = site_url.split('#').next().unwrap_or_default();
+        format!("{}#{}", site_url, self.encode().join("/"))
+    }
+}
+
+fn encode_expected(expected: Option<&str>) -> Vec<u8> {
+    expected.map_or_else(Vec::new, |expected| {
+        format!("{}{}", EXPECTED_MARKER, expected).into_bytes()
+    })
+}
+
+fn decode_expected(field: Vec<u8>) -> Result<Option<String>, DecodeError> {
+    Ok(text(field)?
+        .strip_prefix(EXPECTED_MARKER)
+        .map(ToString::to_string))
+}
+
+fn decode_tests(mut fields: impl Iterator<Item = Vec<u8>>) -> Result<Vec<TestCase>, DecodeError> {
+    let mut tests = vec![];
+    while let Some(input) = fields.next() {
+        let mut next = || fields.next().unwrap_or_default();
+        let args = text(next())?;
+        let expected = decode_expected(next())?;
+        tests.push(TestCase {
+            input,
+            args,
+            expected,
+        });
+    }
+    Ok(tests)
+}
+
+/// Only the code and the inputs may hold arbitrary bytes; every other field is text.
+fn text(field: Vec<u8>) -> Result<String, DecodeError> {
+    String::from_utf8(field).map_err(|_| DecodeError::InvalidUtf8)
+}
+
+/// Prefixes every field with its length.
+fn payload(fields: &[Vec<u8>]) -> Vec<u8> {
+    let mut payload = vec![];
+    for field in fields {
+        write_varint(&mut payload, field.len());
+        payload.extend_from_slice(field);
+    }
+    payload
+}
+
+fn decode_fields(data: &str) -> Result<Vec<Vec<u8>>, DecodeError> {
+    let data = BASE64
+        .decode(data.as_bytes())
+        .map_err(|_| DecodeError::InvalidBase64)?;
+    if data.len() < 4 {
+        return Err(DecodeError::Truncated);
+    }
+    let (compressed, checksum) = data.split_at(data.len() - 4);
+    let payload = decompress_to_vec_with_limit(compressed, MAX_DECODED_SIZE)
+        .map_err(|_| DecodeError::InvalidCompression)?;
+    if crc32fast::hash(&payload).to_be_bytes() != checksum {
+        return Err(DecodeError::ChecksumMismatch);
+    }
+
+    let mut fields = vec![];
+    let mut rest = &payload[..];
+    while !rest.is_empty() {
+        let len = read_varint(&mut rest).ok_or(DecodeError::Truncated)?;
+        if len > rest.len() {
+            return Err(DecodeError::Truncated);
+        }
+        let (field, tail) = rest.split_at(len);
+        fields.push(field.to_vec());
+        rest = tail;
+    }
+    Ok(fields)
+}
+
+fn decode_legacy_field(s: &str) -> Result<Vec<u8>, DecodeError> {
+    let s = s.strip_prefix('@').ok_or(DecodeError::InvalidBase64)?;
+    BASE64
+        .decode(s.as_bytes())
+        .map_err(|_| DecodeError::InvalidBase64)
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut n: usize) {
+    while n >= 0x80 {
+        buf.push((n & 0x7f) as u8 | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn read_varint(buf: &mut &[u8]) -> Option<usize> {
+    let mut n = 0_usize;
+    for shift in (0..usize::BITS).step_by(7) {
+        let (&byte, rest) = buf.split_first()?;
+        *buf = rest;
+        n |= usize::from(byte & 0x7f).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(n);
+        }
+    }
+    None
+}
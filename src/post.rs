@@ -0,0 +1,227 @@
+#![allow(clippy::must_use_candidate)]
+use indoc::indoc;
+use std::fmt::Write;
+
+/// Placeholders understood by `render`, as shown to users writing their own template.
+pub const PLACEHOLDERS: &[&str] = &[
+    "{lang}",
+    "{homepage}",
+    "{bytes}",
+    "{s}",
+    "{code}",
+    "{fence}",
+    "{code_indented}",
+    "{code_html}",
+    "{link}",
+    "{hash}",
+];
+
+/// Everything a post template can refer to.
+pub struct Post<'a> {
+    pub lang: &'a str,
+    pub homepage: &'a str,
+    pub code: &'a str,
+    /// The length of the submitted code, which `code` doesn't tell if the code is not UTF-8
+    pub bytes: usize,
+    pub link: &'a str,
+    /// The program fingerprint, see `Permalink::fingerprint`
+    pub hash: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Template {
+    Default,
+    Leaderboard,
+    Explanation,
+    Html,
+    GitHub,
+    Custom,
+}
+
+impl Template {
+    pub const ALL: [Self; 6] = [
+        Self::Default,
+        Self::Leaderboard,
+        Self::Explanation,
+        Self::Html,
+        Self::GitHub,
+        Self::Custom,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Default => "CGCC",
+            Self::Leaderboard => "CGCC (leaderboard header)",
+            Self::Explanation => "CGCC with explanation",
+            Self::Html => "CGCC with HTML code block",
+            Self::GitHub => "GitHub Markdown",
+            Self::Custom => "Custom",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|t| t.name() == name)
+    }
+
+    /// The template text, or `None` for `Custom`, whose text is supplied by the user.
+    pub const fn text(self) -> Option<&'static str> {
+        match self {
+            Self::Default => Some(indoc!(
+                r#"
+                # [{lang}][tib-{lang}], {bytes} byte{s}
+
+                {fence}
+                {code}
+                {fence}
+
+                [Try in browser!][tib-{hash}]
+
+                [tib-{lang}]: {homepage}
+                [tib-{hash}]: {link}
+                "#
+            )),
+            Self::Leaderboard => Some(indoc!(
+                r#"
+                # {lang}, {bytes} byte{s}
+
+                {fence}
+                {code}
+                {fence}
+
+                [Try in browser!]({link})
+                "#
+            )),
+            Self::Explanation => Some(indoc!(
+                r#"
+                # [{lang}][tib-{lang}], {bytes} byte{s}
+
+                {fence}
+                {code}
+                {fence}
+
+                [Try in browser!][tib-{hash}]
+
+                ## Explanation
+
+
+
+                [tib-{lang}]: {homepage}
+                [tib-{hash}]: {link}
+                "#
+            )),
+            Self::Html => Some(indoc!(
+                r#"
+                # [{lang}][tib-{lang}], {bytes} byte{s}
+
+                <pre><code>{code_html}</code></pre>
+
+                [Try in browser!][tib-{hash}]
+
+                [tib-{lang}]: {homepage}
+                [tib-{hash}]: {link}
+                "#
+            )),
+            Self::GitHub => Some(indoc!(
+                r#"
+                **[{lang}]({homepage})**, {bytes} byte{s}
+
+                {fence}
+                {code}
+                {fence}
+
+                [Try in browser!]({link})
+                "#
+            )),
+            Self::Custom => None,
+        }
+    }
+}
+
+/// Fills in the placeholders of `template`. Anything else in braces is left as is.
+///
+/// `{bytes}` is `Post::bytes`, the length of the submitted code, however it ends up being
+/// rendered.
+/// `{fence}` is a backtick fence longer than any backtick run in the code,
+/// so that the code can never close its own block.
+/// Markdown has no escape for tabs, so `{code}` and `{code_indented}` keep them as they are,
+/// and sites that expand tabs in code blocks show spaces instead; only `{code_html}` keeps
+/// tabs intact, as character references.
+pub fn render(template: &str, post: &Post) -> String {
+    let bytes = post.bytes;
+    let mut rendered = String::with_capacity(template.len() + post.code.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered += &rest[..start];
+        rest = &rest[start..];
+        let end = rest.find('}').map_or(rest.len(), |end| end + 1);
+        match &rest[..end] {
+            "{lang}" => rendered += post.lang,
+            "{homepage}" => rendered += post.homepage,
+            "{bytes}" => rendered += &bytes.to_string(),
+            "{s}" => rendered += if bytes == 1 { "" } else { "s" },
+            "{code}" => rendered += post.code,
+            "{fence}" => rendered += &fence(post.code),
+            "{code_indented}" => rendered += &indent(post.code),
+            "{code_html}" => rendered += &escape_html(post.code),
+            "{link}" => rendered += post.link,
+            "{hash}" => {
+                let _res = write!(rendered, "{:016x}", post.hash);
+            }
+            // Not a placeholder, so only the brace is consumed
+            _ => {
+                rendered.push('{');
+                rest = &rest[1..];
+                continue;
+            }
+        }
+        rest = &rest[end..];
+    }
+    rendered + rest
+}
+
+/// Converts a UTF-16 selection range, as reported by a textarea, to the selected part of `code`.
+/// Offsets past the end or inside a character are clamped.
+pub fn selected_code(code: &str, start: usize, end: usize) -> &str {
+    let byte_offset = |utf16_offset: usize| {
+        let mut units = 0;
+        for (i, c) in code.char_indices() {
+            if units >= utf16_offset {
+                return i;
+            }
+            units += c.len_utf16();
+        }
+        code.len()
+    };
+    let (start, end) = (byte_offset(start), byte_offset(end));
+    &code[start..end.max(start)]
+}
+
+fn fence(code: &str) -> String {
+    let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest_run.max(2) + 1)
+}
+
+fn indent(code: &str) -> String {
+    code.split('\n')
+        .map(|line| format!("    {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escapes text for HTML content and attribute values.
+/// Tabs are written as character references,
+/// because sites that expand tabs in post sources would change the byte count.
+pub fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped += "&amp;",
+            '<' => escaped += "&lt;",
+            '>' => escaped += "&gt;",
+            '"' => escaped += "&quot;",
+            '\t' => escaped += "&#9;",
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
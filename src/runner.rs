@@ -1,198 +1,197 @@
 use js_sys::Date;
 use seed::log;
+use std::future::Future;
+use std::rc::Rc;
 use threading::prelude::*;
-use threading::Thread;
-use wasm_bindgen_futures::spawn_local;
+use threading::{Thread, WorkerPool};
+use wasm_bindgen::JsValue;
 use web_sys::window;
 
+use crate::runtime::controller::{RunController, RunId, RunState};
+use crate::runtime::limits::OutputLimits;
+use crate::runtime::protocol::Job;
+use crate::runtime::scheduler::{JobResult, Scheduler};
 use crate::threading;
 
-struct RunnerState {
-    mt: Option<WasmMt>,
-    thread: Option<Thread>,
-    th_init: bool,
-    result: Option<(String, String)>,
-    th_finished: bool,
-    th_crashed: bool,
-    start_time: f64,
-}
+/// Number of initialized workers kept ready, so that a run never waits for a worker to start,
+/// even right after the previous worker was terminated.
+const IDLE_WORKERS: usize = 2;
 
-static mut STATE: RunnerState = RunnerState {
-    mt: None,
-    thread: None,
-    th_init: false,
-    result: None,
-    th_finished: false,
-    th_crashed: false,
-    start_time: 0.0,
-};
-
-fn set_mt(mt: WasmMt) {
-    unsafe {
-        STATE.mt = Some(mt);
-    }
-}
+/// The most workers a run uses at once, however many cores there are.
+const MAX_WORKERS: usize = 8;
 
-fn get_mt() -> Option<&'static WasmMt> {
-    unsafe { STATE.mt.as_ref() }
+/// A worker handed out for a run, to be passed to `Runner::started`.
+pub struct Started {
+    run: RunId,
+    thread: Result<Thread, JsValue>,
 }
 
-fn reset_thread() {
-    unsafe {
-        STATE.thread = None;
-    }
+/// Runs batches of jobs on workers, several at a time. Owned by the model; the futures it
+/// hands out report back through messages, and everything else is picked up by `poll`.
+#[derive(Default)]
+pub struct Runner {
+    controller: RunController,
+    pool: Option<Rc<WorkerPool>>,
+    // The current or last batch, kept so that its results can be read
+    batch: Option<(RunId, Scheduler<Thread>)>,
 }
 
-fn set_thread(th: Thread) {
-    unsafe {
-        STATE.thread = Some(th);
-    }
-}
-
-fn get_thread() -> Option<&'static Thread> {
-    unsafe { STATE.thread.as_ref() }
-}
-
-fn set_th_init(b: bool) {
-    unsafe {
-        STATE.th_init = b;
+/// Sets up the workers; the runner can be used once the pool is passed to `Runner::set_pool`.
+pub fn init() -> impl Future<Output = Rc<WorkerPool>> {
+    init_thread_impls();
+    async {
+        let pkg_js = "./pkg/package.js";
+        let mt = WasmMt::new(pkg_js).and_init().await.unwrap();
+        log!("mt init success");
+        WorkerPool::new(mt, IDLE_WORKERS, max_workers())
     }
 }
 
-fn get_th_init() -> bool {
-    unsafe { STATE.th_init }
+fn now() -> f64 {
+    window()
+        .and_then(|w| w.performance())
+        .map_or(Date::now(), |p| p.now())
 }
 
-fn set_th_finished(b: bool) {
-    unsafe {
-        STATE.th_finished = b;
-    }
+/// One worker per core, leaving one for the UI.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn max_workers() -> usize {
+    let cores = window().map_or(1.0, |w| w.navigator().hardware_concurrency()) as usize;
+    cores.saturating_sub(1).clamp(1, MAX_WORKERS)
 }
 
-pub fn get_th_finished() -> bool {
-    unsafe {
-        let ret = STATE.th_finished;
-        STATE.th_finished = false;
-        ret
+impl Runner {
+    pub fn set_pool(&mut self, pool: Rc<WorkerPool>) {
+        self.pool = Some(pool);
     }
-}
 
-fn set_th_crashed(b: bool) {
-    unsafe {
-        STATE.th_crashed = b;
+    pub const fn state(&self) -> RunState {
+        self.controller.state()
     }
-}
 
-pub fn get_th_crashed() -> bool {
-    unsafe {
-        let ret = STATE.th_crashed;
-        STATE.th_crashed = false;
-        ret
+    /// Whether a run can be started.
+    pub fn is_ready(&self) -> bool {
+        self.pool.is_some() && self.state() == RunState::Idle
     }
-}
 
-pub fn reset_all_flags() {
-    get_th_finished();
-    get_th_crashed();
-}
-
-fn reset_result() {
-    unsafe {
-        if let Some((ref mut x, ref mut y)) = STATE.result {
-            x.clear();
-            y.clear();
+    /// Starts running a batch of jobs. Workers for it must then be requested with
+    /// `request_workers`. Returns whether it started.
+    pub fn start(&mut self, jobs: Vec<Job>, limits: OutputLimits) -> bool {
+        if self.pool.is_none() {
+            return false;
+        }
+        if let Some(run) = self.controller.start() {
+            self.batch = Some((run, Scheduler::new(jobs, limits, max_workers())));
+            true
         } else {
-            STATE.result = Some((String::new(), String::new()));
+            false
         }
     }
-}
 
-fn set_result(s1: String, s2: String) {
-    unsafe {
-        STATE.result = Some((s1, s2));
+    /// Asks the pool for the workers the batch can use. Each of the returned futures
+    /// waits for one of them.
+    pub fn request_workers(&mut self) -> Vec<impl Future<Output = Started>> {
+        let (pool, (run, scheduler)) = match (&self.pool, &mut self.batch) {
+            (Some(pool), Some(batch)) if self.controller.state() != RunState::Idle => (pool, batch),
+            _ => return vec![],
+        };
+        let run = *run;
+        (0..scheduler.request_workers())
+            .map(|_| {
+                let pool = Rc::clone(pool);
+                async move {
+                    let thread = pool.take().await;
+                    Started { run, thread }
+                }
+            })
+            .collect()
     }
-}
 
-fn set_start_time() {
-    unsafe {
-        STATE.start_time = window()
-            .and_then(|w| w.performance())
-            .map_or(Date::now(), |p| p.now());
+    /// Puts a worker to use, or gives it back if the batch was stopped in the meantime.
+    /// Returns the jobs that ended, which happens if no worker could be started for them.
+    pub fn started(&mut self, started: Started) -> Vec<usize> {
+        let Started { run, thread } = started;
+        let accepted = self.controller.started(run);
+        let scheduler = match &mut self.batch {
+            Some((_, scheduler)) if accepted => scheduler,
+            _ => {
+                self.give_back(thread.ok());
+                return vec![];
+            }
+        };
+        let ended = match thread {
+            Ok(thread) => {
+                let unused = scheduler.add_worker(thread, now());
+                self.give_back(unused);
+                vec![]
+            }
+            Err(err) => {
+                log!("failed to get a worker", err);
+                scheduler.worker_failed(now())
+            }
+        };
+        self.finish_if_done();
+        ended
     }
-}
 
-pub fn get_elapsed_time() -> f64 {
-    unsafe {
-        let end_time = window()
-            .and_then(|w| w.performance())
-            .map_or(Date::now(), |p| p.now());
-        (end_time - STATE.start_time) / 1000.0
+    /// Picks up the output and outcomes that have arrived. Returns the jobs that ended.
+    pub fn poll(&mut self) -> Vec<usize> {
+        if self.state() != RunState::Running {
+            return vec![];
+        }
+        let ended = self
+            .batch
+            .as_mut()
+            .map_or_else(Vec::new, |(_, scheduler)| scheduler.poll(now()));
+        self.finish_if_done();
+        ended
     }
-}
 
-pub fn take_result_from_thread() -> (String, String) {
-    unsafe {
-        STATE.thread.as_mut().map_or_else(
-            || (String::new(), String::new()),
-            |th| {
-                let s1 = th.stdout();
-                let s2 = th.stderr();
-                (s1, s2)
-            },
-        )
+    fn finish_if_done(&mut self) {
+        let idle = match &mut self.batch {
+            Some((run, scheduler)) if scheduler.is_done() && self.controller.finished(*run) => {
+                scheduler.take_idle_workers()
+            }
+            _ => return,
+        };
+        for thread in idle {
+            self.give_back(Some(thread));
+        }
     }
-}
 
-pub fn init() {
-    spawn_local(async {
-        let pkg_js = "./pkg/package.js";
-        let mt = WasmMt::new(pkg_js).and_init().await.unwrap();
-        log!("success");
-        set_mt(mt);
-        log!("success 2");
-    });
-    init_thread_impls();
-}
-
-pub fn poll_mt_init() -> bool {
-    if get_mt().is_some() && !get_th_init() {
-        log!("mt init success");
-        set_th_init(true);
+    fn give_back(&self, thread: Option<Thread>) {
+        if let (Some(thread), Some(pool)) = (thread, &self.pool) {
+            pool.give_back(thread);
+        }
     }
-    get_th_init()
-}
 
-pub fn run(lang: &str, code: &str, stdin: &str, args: &str) {
-    reset_result();
-    let lang = lang.to_string();
-    let code = code.to_string();
-    let stdin = stdin.to_string();
-    let args = args.to_string();
-    spawn_local(async move {
-        let mt = get_mt().unwrap();
-        let th = mt.thread().and_init().await.unwrap();
-        log!("reset success");
-        set_thread(th);
-        log!("reset success 2");
-        let thread = get_thread().unwrap();
-        let start_time: f64 = Date::now();
-        set_start_time();
-        let result = exec_lang!(thread, &lang, &code, &stdin, &args).await;
-        let end_time: f64 = Date::now();
-        let elapsed = (end_time - start_time) / 1000.0;
-        log!(result, elapsed);
-        if let Ok(_jsval) = result {
-            log!("finished");
-            set_th_finished(true);
-        } else {
-            set_result("".to_string(), "err found".to_string());
-            set_th_crashed(true);
+    /// Stops the batch, terminating its workers. Returns the jobs that ended.
+    pub fn stop(&mut self) -> Vec<usize> {
+        if !self.controller.stop() {
+            return vec![];
         }
-    });
-}
+        let (cancelled, idle) = self.batch.as_mut().map_or_else(
+            || (vec![], vec![]),
+            |(_, scheduler)| (scheduler.cancel(now()), scheduler.take_idle_workers()),
+        );
+        for thread in idle {
+            self.give_back(Some(thread));
+        }
+        // Start replacements for the terminated workers right away
+        if let Some(pool) = &self.pool {
+            pool.warm_up();
+        }
+        cancelled
+    }
 
-pub fn reset() {
-    let thread = get_thread().expect("A thread should be active");
-    thread.terminate();
-    reset_thread();
+    /// The output of a job of the current or last batch so far.
+    pub fn result(&self, job: usize) -> Option<&JobResult> {
+        self.batch
+            .as_ref()
+            .map(|(_, scheduler)| scheduler.result(job))
+    }
+
+    pub fn get_elapsed_time(&self, job: usize) -> f64 {
+        self.result(job).map_or(0.0, |result| result.elapsed(now()))
+    }
 }
@@ -0,0 +1,95 @@
+/// Output written since the last flush, as `(stdout, stderr)` segments in the order written.
+/// Within a segment, the stdout part comes first.
+pub type Batch = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// Flush output at least this often while the program keeps writing.
+pub const FLUSH_INTERVAL_MS: f64 = 20.0;
+/// Flush output once this many bytes are pending.
+pub const FLUSH_BYTES: usize = 16 * 1024;
+/// Reading the clock is slow in a worker, so while output is held back it is only read
+/// again after this many writes.
+pub const CHECK_WRITES: usize = 64;
+
+/// Coalesces the many small writes of an interpreter into few messages.
+///
+/// A worker runs the interpreter synchronously, so nothing can be sent on a timer;
+/// instead writes check whether a flush is due. The first write after a flush always does,
+/// so that sparse output is still sent right away.
+/// Output held back by the last writes of a burst is sent by `poll`, which the writer calls
+/// every so many steps.
+pub struct OutputBatcher {
+    segments: Batch,
+    pending: usize,
+    last_flush: Option<f64>,
+    // Writes held back since the clock was last read; the next write reads it if zero
+    unchecked: usize,
+}
+
+impl Default for OutputBatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputBatcher {
+    pub const fn new() -> Self {
+        Self {
+            segments: vec![],
+            pending: 0,
+            last_flush: None,
+            unchecked: 0,
+        }
+    }
+
+    /// Adds a write and returns the batch to send, if due. `now` reads the clock, in
+    /// milliseconds, and is only called when the batcher needs the time.
+    pub fn push(&mut self, out: &[u8], err: &[u8], now: impl FnOnce() -> f64) -> Option<Batch> {
+        if !out.is_empty() || !err.is_empty() {
+            self.pending += out.len() + err.len();
+            match self.segments.last_mut() {
+                // Appending keeps the order: the last segment's stdout still comes first
+                Some((last_out, last_err)) if last_err.is_empty() => {
+                    last_out.extend_from_slice(out);
+                    last_err.extend_from_slice(err);
+                }
+                Some((_, last_err)) if out.is_empty() => last_err.extend_from_slice(err),
+                _ => self.segments.push((out.to_vec(), err.to_vec())),
+            }
+        }
+        if self.pending >= FLUSH_BYTES {
+            self.flush(now())
+        } else if self.unchecked > 0 && self.unchecked < CHECK_WRITES {
+            self.unchecked += 1;
+            None
+        } else {
+            self.poll(now())
+        }
+    }
+
+    /// Returns the batch to send at time `now`, if one is due without any new write.
+    pub fn poll(&mut self, now: f64) -> Option<Batch> {
+        let recently_flushed = matches!(
+            self.last_flush,
+            Some(last_flush) if now - last_flush < FLUSH_INTERVAL_MS
+        );
+        if recently_flushed {
+            if !self.segments.is_empty() {
+                self.unchecked = 1;
+            }
+            None
+        } else {
+            self.flush(now)
+        }
+    }
+
+    /// Returns everything pending, if anything is. Must be called when the program ends.
+    pub fn flush(&mut self, now: f64) -> Option<Batch> {
+        if self.segments.is_empty() {
+            return None;
+        }
+        self.last_flush = Some(now);
+        self.pending = 0;
+        self.unchecked = 0;
+        Some(std::mem::take(&mut self.segments))
+    }
+}
@@ -0,0 +1,107 @@
+/// The stages of a run, as seen by the UI.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunState {
+    /// Nothing is running; a run may start
+    Idle,
+    /// Waiting for a worker to run the program on
+    Starting,
+    /// The program is running on its workers
+    Running,
+    /// Stopped while waiting for a worker, which can't be cancelled; waiting for it to arrive
+    /// so that it can be put back
+    Stopping,
+}
+
+/// Identifies a run, so that news about an earlier run is never mistaken for the current one's.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunId(u64);
+
+/// Decides what to do as a run starts, ends or is stopped.
+///
+/// Every event is accepted in every state; events that don't apply, such as a stop when nothing
+/// is running or the end of a run that was already stopped, are ignored and reported as such.
+#[derive(Debug)]
+pub struct RunController {
+    state: RunState,
+    last_run: u64,
+}
+
+impl Default for RunController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RunController {
+    pub const fn new() -> Self {
+        Self {
+            state: RunState::Idle,
+            last_run: 0,
+        }
+    }
+
+    pub const fn state(&self) -> RunState {
+        self.state
+    }
+
+    const fn is_current(&self, run: RunId) -> bool {
+        run.0 == self.last_run
+    }
+
+    /// Starts a new run, unless one is already going on.
+    pub fn start(&mut self) -> Option<RunId> {
+        if self.state != RunState::Idle {
+            return None;
+        }
+        self.last_run += 1;
+        self.state = RunState::Starting;
+        Some(RunId(self.last_run))
+    }
+
+    /// Reports that a worker is ready for `run`, which may use several. Returns whether
+    /// the run should use it; if not, the worker is unused and can be put back.
+    pub fn started(&mut self, run: RunId) -> bool {
+        if !self.is_current(run) {
+            return false;
+        }
+        match self.state {
+            RunState::Starting => {
+                self.state = RunState::Running;
+                true
+            }
+            RunState::Running => true,
+            RunState::Stopping => {
+                self.state = RunState::Idle;
+                false
+            }
+            RunState::Idle => false,
+        }
+    }
+
+    /// Stops the current run. Returns whether there was one to stop; if it was `Running`,
+    /// its workers must be terminated.
+    pub fn stop(&mut self) -> bool {
+        match self.state {
+            RunState::Starting => {
+                self.state = RunState::Stopping;
+                true
+            }
+            RunState::Running => {
+                self.state = RunState::Idle;
+                true
+            }
+            RunState::Idle | RunState::Stopping => false,
+        }
+    }
+
+    /// Reports that `run` ended by itself, normally or not. Returns whether it was still
+    /// the current run; if not, it was stopped and has already been dealt with.
+    pub fn finished(&mut self, run: RunId) -> bool {
+        if self.is_current(run) && self.state == RunState::Running {
+            self.state = RunState::Idle;
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use super::batch::OutputBatcher;
+use super::progress::ProgressTracker;
+use super::protocol::{Job, ProtocolError, Request, Response};
+use crate::lang::{interpret_version, LangWriter, Stats};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Where a worker sends its responses, as seen from the side that runs jobs.
+/// The counterpart of `Transport`.
+pub trait ResponseSink: 'static {
+    fn respond(&self, response: Response);
+
+    /// The current time in milliseconds, for batching output and timing heartbeats.
+    fn now(&self) -> f64;
+
+    /// Called on every write and every so many steps of the running job. A sink whose
+    /// worker can't be stopped from outside may unwind from here to stop the job once it is
+    /// terminated.
+    fn check_terminated(&self) {}
+}
+
+type CrashReporter = Box<dyn Fn(&str)>;
+
+thread_local! {
+    // Reports a crash of the running job, for the panic hook
+    static RUNNING: RefCell<Option<CrashReporter>> = RefCell::new(None);
+}
+
+/// Sends the output of a job to a sink, batched, along with heartbeats.
+pub struct JobWriter<S> {
+    sink: Rc<S>,
+    stats: Option<Stats>,
+    batcher: Rc<RefCell<OutputBatcher>>,
+    tracker: ProgressTracker,
+    // Interpreters may report an error and then return normally; only the first end counts
+    terminated: bool,
+}
+
+impl<S: ResponseSink> JobWriter<S> {
+    pub fn new(sink: Rc<S>) -> Self {
+        Self {
+            tracker: ProgressTracker::new(sink.now()),
+            sink,
+            stats: None,
+            batcher: Rc::new(RefCell::new(OutputBatcher::new())),
+            terminated: false,
+        }
+    }
+
+    fn flush(&self) {
+        let batch = self.batcher.borrow_mut().flush(self.sink.now());
+        if let Some(batch) = batch {
+            self.sink.respond(Response::Output(batch));
+        }
+    }
+}
+
+impl<S: ResponseSink> LangWriter for JobWriter<S> {
+    fn write_both(&mut self, out: &[u8], err: &[u8]) {
+        self.sink.check_terminated();
+        self.tracker.write(out, err);
+        let sink = &self.sink;
+        let batch = self.batcher.borrow_mut().push(out, err, || sink.now());
+        if let Some(batch) = batch {
+            self.sink.respond(Response::Output(batch));
+        }
+    }
+    fn write_stats(&mut self, stats: &Stats) {
+        self.stats = Some(stats.clone());
+    }
+    fn progress(&mut self, steps: u64, ip: Option<usize>) {
+        if !self.tracker.step(steps, ip) {
+            return;
+        }
+        self.sink.check_terminated();
+        let now = self.sink.now();
+        if let Some(progress) = self.tracker.heartbeat(now) {
+            // Output held back by batching is sent first, so that it is as recent as the progress
+            self.flush();
+            self.sink.respond(Response::Heartbeat(progress));
+        } else {
+            // Releases the end of a burst of output while the program goes on silently
+            let batch = self.batcher.borrow_mut().poll(now);
+            if let Some(batch) = batch {
+                self.sink.respond(Response::Output(batch));
+            }
+        }
+    }
+    fn terminate(&mut self) {
+        if self.terminated {
+            return;
+        }
+        self.terminated = true;
+        self.flush();
+        if let Some(stats) = self.stats.take() {
+            self.sink.respond(Response::Stats(stats));
+        }
+        self.sink.respond(Response::Finished);
+    }
+    fn terminate_with_error(&mut self, msg: &str) {
+        if self.terminated {
+            return;
+        }
+        self.terminated = true;
+        self.write_err(msg);
+        self.flush();
+        self.sink.respond(Response::Failed(msg.to_string()));
+    }
+}
+
+/// Reports a crash of the job running on this thread as an error, with `msg` added to
+/// its stderr. Meant to be called from a panic hook; returns whether a job was running.
+pub fn report_crash(msg: &str) -> bool {
+    RUNNING.with(|running| {
+        if let Some(report) = running.borrow().as_ref() {
+            report(msg);
+            true
+        } else {
+            false
+        }
+    })
+}
+
+// Forgets the running job even if it unwinds
+struct RunningGuard;
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        RUNNING.with(|running| *running.borrow_mut() = None);
+    }
+}
+
+fn run_job<S: ResponseSink>(job: &Job, sink: &Rc<S>) {
+    let mut writer = JobWriter::new(sink.clone());
+    let (sink, batcher) = (sink.clone(), writer.batcher.clone());
+    let report: CrashReporter = Box::new(move |msg| {
+        // The output is left alone if the crash happened while it was being written
+        if let Ok(mut batcher) = batcher.try_borrow_mut() {
+            let err = format!("\n{}", msg);
+            let batch = batcher.push(b"", err.as_bytes(), || sink.now());
+            if let Some(batch) = batch.or_else(|| batcher.flush(sink.now())) {
+                sink.respond(Response::Output(batch));
+            }
+        }
+        sink.respond(Response::Error(msg.to_string()));
+    });
+    RUNNING.with(|running| *running.borrow_mut() = Some(report));
+    let _guard = RunningGuard;
+    interpret_version(
+        &job.lang,
+        job.version.as_deref(),
+        &job.code,
+        &job.input,
+        &job.args,
+        &mut writer,
+    );
+}
+
+/// Handles a request sent to a worker, answering through `sink`. `pending_input` keeps
+/// the input provided ahead of the next job between requests.
+pub fn handle_request<S: ResponseSink>(
+    sink: &Rc<S>,
+    pending_input: &RefCell<Vec<u8>>,
+    request: Result<Request, ProtocolError>,
+) {
+    match request {
+        Ok(Request::Init) => sink.respond(Response::Ready),
+        Ok(Request::Run(mut job)) => {
+            let mut input = pending_input.replace(vec![]);
+            input.append(&mut job.input);
+            job.input = input;
+            run_job(&job, sink);
+        }
+        Ok(Request::ProvideInput(mut input)) => {
+            pending_input.borrow_mut().append(&mut input);
+            sink.respond(Response::Ready);
+        }
+        Ok(Request::Cancel) => {
+            pending_input.borrow_mut().clear();
+            sink.respond(Response::Ready);
+        }
+        Err(err) => sink.respond(Response::Error(err.to_string())),
+    }
+}
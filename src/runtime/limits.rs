@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+
+/// The default limit for each of stdout and stderr, in bytes.
+pub const DEFAULT_LIMIT: usize = 128 * 1024;
+
+/// What to do when a program writes more than the limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverflowPolicy {
+    /// Stop the program, keeping the output up to the limit
+    Abort,
+    /// Keep running, but only keep the first part of the output
+    KeepHead,
+    /// Keep running, but only keep the last part of the output
+    KeepTail,
+    /// Keep running, but throw away all of the output
+    Discard,
+}
+
+impl OverflowPolicy {
+    pub const ALL: [Self; 4] = [Self::Abort, Self::KeepHead, Self::KeepTail, Self::Discard];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Abort => "abort",
+            Self::KeepHead => "keep head",
+            Self::KeepTail => "keep tail",
+            Self::Discard => "discard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|policy| policy.name() == name)
+    }
+}
+
+/// The output limits of a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputLimits {
+    pub stdout: usize,
+    pub stderr: usize,
+    pub policy: OverflowPolicy,
+}
+
+impl Default for OutputLimits {
+    fn default() -> Self {
+        Self {
+            stdout: DEFAULT_LIMIT,
+            stderr: DEFAULT_LIMIT,
+            policy: OverflowPolicy::Abort,
+        }
+    }
+}
+
+impl OutputLimits {
+    /// Limiters for stdout and stderr, in that order.
+    pub const fn limiters(self) -> (OutputLimiter, OutputLimiter) {
+        (
+            OutputLimiter::new(self.stdout, self.policy),
+            OutputLimiter::new(self.stderr, self.policy),
+        )
+    }
+}
+
+/// Applies a limit to one output stream as it arrives.
+#[derive(Clone, Debug)]
+pub struct OutputLimiter {
+    limit: usize,
+    policy: OverflowPolicy,
+    // Bytes written so far, including the ones that were not kept
+    written: usize,
+}
+
+impl OutputLimiter {
+    pub const fn new(limit: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            limit,
+            policy,
+            written: 0,
+        }
+    }
+
+    pub const fn is_overflown(&self) -> bool {
+        self.written > self.limit
+    }
+
+    /// Appends `bytes` to `dest`, which holds the output kept so far, dropping whatever
+    /// the policy says. Returns `false` if the program must be aborted.
+    pub fn push(&mut self, dest: &mut Vec<u8>, bytes: &[u8]) -> bool {
+        self.written += bytes.len();
+        if !self.is_overflown() {
+            dest.extend_from_slice(bytes);
+            return true;
+        }
+        match self.policy {
+            OverflowPolicy::Abort | OverflowPolicy::KeepHead => {
+                let room = self.limit.saturating_sub(dest.len());
+                dest.extend_from_slice(&bytes[..floor_char_boundary(bytes, room)]);
+            }
+            OverflowPolicy::KeepTail => {
+                dest.extend_from_slice(bytes);
+                let excess = dest.len().saturating_sub(self.limit);
+                let start = ceil_char_boundary(dest, excess);
+                dest.drain(..start);
+            }
+            OverflowPolicy::Discard => dest.clear(),
+        }
+        self.policy != OverflowPolicy::Abort
+    }
+
+    /// Describes what was dropped, if anything.
+    pub fn note(&self, stream: &str) -> Option<String> {
+        if !self.is_overflown() {
+            return None;
+        }
+        let what = match self.policy {
+            OverflowPolicy::Abort => return None,
+            OverflowPolicy::KeepHead => "only the first part is shown",
+            OverflowPolicy::KeepTail => "only the last part is shown",
+            OverflowPolicy::Discard => "it was discarded",
+        };
+        Some(format!(
+            "{} exceeded the limit of {} bytes ({} bytes written); {}",
+            stream, self.limit, self.written, what
+        ))
+    }
+}
+
+// Output is cut between UTF-8 characters where it is text. Other bytes are cut anywhere,
+// so at most the 3 continuation bytes of a character are skipped.
+fn is_char_boundary(bytes: &[u8], index: usize) -> bool {
+    !matches!(bytes.get(index), Some(&byte) if byte & 0xc0 == 0x80)
+}
+
+fn floor_char_boundary(bytes: &[u8], index: usize) -> usize {
+    if index >= bytes.len() {
+        return bytes.len();
+    }
+    (index.saturating_sub(3)..=index)
+        .rev()
+        .find(|&i| is_char_boundary(bytes, i))
+        .unwrap_or(index)
+}
+
+fn ceil_char_boundary(bytes: &[u8], index: usize) -> usize {
+    (index..bytes.len().min(index + 4))
+        .find(|&i| is_char_boundary(bytes, i))
+        .unwrap_or(index)
+}
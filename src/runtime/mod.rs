@@ -0,0 +1,15 @@
+#![allow(clippy::module_name_repetitions)]
+#![allow(clippy::must_use_candidate)]
+// The parts of running programs that don't depend on web workers,
+// so that they can be shared with native code and tested with `cargo test`.
+pub mod batch;
+pub mod controller;
+pub mod job;
+pub mod limits;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod native;
+pub mod panic;
+pub mod progress;
+pub mod protocol;
+pub mod scheduler;
+pub mod transport;
@@ -0,0 +1,150 @@
+use super::job::{self, ResponseSink};
+use super::panic;
+use super::protocol::{Request, Response};
+use super::transport::Transport;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Once};
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+/// Unwinds a terminated worker's job; never reported as a crash.
+struct Terminated;
+
+struct ChannelSink {
+    responses: Sender<Response>,
+    start_time: Instant,
+    terminated: Arc<AtomicBool>,
+}
+
+impl ResponseSink for ChannelSink {
+    fn respond(&self, response: Response) {
+        let _res = self.responses.send(response);
+    }
+
+    fn now(&self) -> f64 {
+        self.start_time.elapsed().as_secs_f64() * 1000.0
+    }
+
+    fn check_terminated(&self) {
+        if self.terminated.load(Ordering::Relaxed) {
+            std::panic::resume_unwind(Box::new(Terminated));
+        }
+    }
+}
+
+/// Reports panics of jobs running on native workers like the web workers' panic hook does,
+/// and leaves other panics to the hook that was there before.
+fn install_panic_hook() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let msg = panic::describe(info.payload(), info.location());
+            if !job::report_crash(&msg) {
+                previous(info);
+            }
+        }));
+    });
+}
+
+/// A worker running jobs on a native thread, so that the job pipeline can be run and tested
+/// without a browser.
+///
+/// Threads can't be killed, so terminating is cooperative: the job is unwound at its next
+/// write or heartbeat. A job of a language that reports no steps and never writes again
+/// keeps its thread busy until the process exits.
+/// A crashed job ends its thread, just like a panic aborts a web worker.
+pub struct NativeWorker {
+    requests: Option<Sender<Request>>,
+    responses: Receiver<Response>,
+    terminated: Arc<AtomicBool>,
+    // Whether the end of the thread was reported
+    disconnected: bool,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Default for NativeWorker {
+    fn default() -> Self {
+        Self::spawn()
+    }
+}
+
+impl NativeWorker {
+    pub fn spawn() -> Self {
+        install_panic_hook();
+        let (requests, request_rx) = channel();
+        let (response_tx, responses) = channel();
+        let terminated = Arc::new(AtomicBool::new(false));
+        let sink = ChannelSink {
+            responses: response_tx,
+            start_time: Instant::now(),
+            terminated: terminated.clone(),
+        };
+        let handle = thread::spawn(move || {
+            let sink = Rc::new(sink);
+            let pending_input = RefCell::new(vec![]);
+            for request in request_rx {
+                let handled = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    job::handle_request(&sink, &pending_input, Ok(request));
+                }));
+                if handled.is_err() {
+                    return;
+                }
+            }
+        });
+        Self {
+            requests: Some(requests),
+            responses,
+            terminated,
+            disconnected: false,
+            handle: Some(handle),
+        }
+    }
+
+    /// Waits for the thread to end, which happens once it is terminated and its job
+    /// writes or steps again, or once it crashes.
+    pub fn join(mut self) {
+        self.requests = None;
+        if let Some(handle) = self.handle.take() {
+            let _res = handle.join();
+        }
+    }
+}
+
+impl Transport for NativeWorker {
+    fn send(&mut self, request: Request) {
+        if let Some(requests) = &self.requests {
+            let _res = requests.send(request);
+        }
+    }
+
+    fn try_recv(&mut self) -> Option<Response> {
+        match self.responses.try_recv() {
+            Ok(response) => Some(response),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                if self.disconnected || self.terminated.load(Ordering::Relaxed) {
+                    None
+                } else {
+                    self.disconnected = true;
+                    Some(Response::Error("the worker crashed".to_string()))
+                }
+            }
+        }
+    }
+
+    fn terminate(&mut self) {
+        self.terminated.store(true, Ordering::Relaxed);
+        // Ends the thread if it is waiting for a request
+        self.requests = None;
+    }
+}
+
+impl Drop for NativeWorker {
+    fn drop(&mut self) {
+        self.terminate();
+    }
+}
@@ -0,0 +1,24 @@
+use std::any::Any;
+use std::panic::Location;
+
+/// Describes a panic the way a crash is reported to users, from the parts of the panic info:
+/// e.g. `panicked at 'index out of bounds', src/lang/brainfuck.rs:12:5`.
+pub fn describe(payload: &(dyn Any + Send), location: Option<&Location>) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+    location.map_or_else(
+        || format!("panicked at '{}'", message),
+        |location| {
+            format!(
+                "panicked at '{}', {}:{}:{}",
+                message,
+                location.file(),
+                location.line(),
+                location.column()
+            )
+        },
+    )
+}
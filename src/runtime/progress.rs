@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+/// Send a heartbeat this often while a program runs, even if it writes nothing.
+pub const HEARTBEAT_INTERVAL_MS: f64 = 250.0;
+/// Reading the clock is slow in a worker, so it is only checked after this many steps.
+pub const CHECK_STEPS: u64 = 1 << 12;
+
+/// How far a running program got.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Progress {
+    pub steps: u64,
+    /// Bytes written to stdout, including any beyond the output limit
+    pub stdout: usize,
+    pub stderr: usize,
+    /// The position of the current instruction in the program, for languages that have one
+    pub ip: Option<usize>,
+}
+
+/// Keeps track of a running program's progress and decides when to report it.
+///
+/// Like output, heartbeats can't be sent on a timer from a worker busy running the
+/// interpreter; instead the clock is checked every so many steps.
+pub struct ProgressTracker {
+    progress: Progress,
+    checked_steps: u64,
+    last_heartbeat: f64,
+}
+
+impl ProgressTracker {
+    /// Starts tracking a program started at time `now` (in milliseconds).
+    pub const fn new(now: f64) -> Self {
+        Self {
+            progress: Progress {
+                steps: 0,
+                stdout: 0,
+                stderr: 0,
+                ip: None,
+            },
+            checked_steps: 0,
+            last_heartbeat: now,
+        }
+    }
+
+    pub const fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    pub fn write(&mut self, out: &[u8], err: &[u8]) {
+        self.progress.stdout += out.len();
+        self.progress.stderr += err.len();
+    }
+
+    /// Records the step count and instruction pointer. Returns whether it is time to check
+    /// the clock with `heartbeat`.
+    pub fn step(&mut self, steps: u64, ip: Option<usize>) -> bool {
+        self.progress.steps = steps;
+        self.progress.ip = ip;
+        if steps.saturating_sub(self.checked_steps) >= CHECK_STEPS {
+            self.checked_steps = steps;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the progress to report at time `now`, if a heartbeat is due.
+    pub fn heartbeat(&mut self, now: f64) -> Option<Progress> {
+        if now - self.last_heartbeat >= HEARTBEAT_INTERVAL_MS {
+            self.last_heartbeat = now;
+            Some(self.progress.clone())
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use super::batch::Batch;
+use super::progress::Progress;
+use crate::lang::Stats;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Bumped whenever a message changes shape, so that a worker left over from another build
+/// reports a mismatch instead of misreading messages.
+pub const PROTOCOL_VERSION: u32 = 5;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Job {
+    pub lang: String,
+    pub version: Option<String>,
+    pub code: Vec<u8>,
+    pub input: Vec<u8>,
+    pub args: String,
+}
+
+/// Messages from the UI to a worker.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum Request {
+    /// Checks that the worker is up; answered with `Ready`
+    Init,
+    /// Answered with any number of `Output`, `Heartbeat` and `Stats`, then `Finished`, `Failed`
+    /// or `Error`
+    Run(Job),
+    /// Appends to the input of the next job, so that large inputs can be sent in pieces;
+    /// answered with `Ready`
+    ProvideInput(Vec<u8>),
+    /// Discards input provided for the next job; answered with `Ready`.
+    /// A job that is already running can only be stopped by terminating its worker.
+    Cancel,
+}
+
+/// Messages from a worker to the UI. `Output` is sent in binary with `encode_output`,
+/// everything else with `encode`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum Response {
+    Ready,
+    Output(Batch),
+    /// Sent every so often while a job runs, so that a silent program can be told
+    /// from a stuck worker
+    Heartbeat(Progress),
+    Stats(Stats),
+    Finished,
+    /// The program stopped with an error of its own, already written to its stderr;
+    /// the worker can run another job
+    Failed(String),
+    /// The worker crashed or could not run the job, and must not be reused
+    Error(String),
+}
+
+impl Response {
+    /// Whether this is the last response to a request.
+    pub const fn is_final(&self) -> bool {
+        !matches!(self, Self::Output(_) | Self::Heartbeat(_) | Self::Stats(_))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProtocolError {
+    Malformed(String),
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Malformed(err) => write!(f, "malformed message: {}", err),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported protocol version {} (expected {})",
+                version, PROTOCOL_VERSION
+            ),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    version: u32,
+    body: T,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+/// Encodes a request or response as JSON, tagged with the protocol version.
+///
+/// # Panics
+///
+/// Never in practice: the messages hold nothing that JSON can't represent.
+pub fn encode<T: Serialize>(body: &T) -> String {
+    serde_json::to_string(&Envelope {
+        version: PROTOCOL_VERSION,
+        body,
+    })
+    .expect("messages are always serializable")
+}
+
+/// Decodes a message made by `encode`.
+///
+/// # Errors
+///
+/// Returns an error if the message is not valid or comes from another protocol version.
+pub fn decode<T: DeserializeOwned>(msg: &str) -> Result<T, ProtocolError> {
+    let malformed = |err: serde_json::Error| ProtocolError::Malformed(err.to_string());
+    let Version { version } = serde_json::from_str(msg).map_err(malformed)?;
+    if version != PROTOCOL_VERSION {
+        return Err(ProtocolError::UnsupportedVersion(version));
+    }
+    let envelope: Envelope<T> = serde_json::from_str(msg).map_err(malformed)?;
+    Ok(envelope.body)
+}
+
+/// Encodes output as bytes, so that it can be moved to the UI in a transferred buffer.
+///
+/// The bytes are the protocol version, then the length and bytes of the stdout and stderr
+/// of each segment, with lengths as little-endian `u32`. Output is kept byte for byte,
+/// whether it is UTF-8 or not.
+///
+/// # Panics
+///
+/// If a segment is 4 GiB or longer, which the batching of output never lets happen.
+pub fn encode_output(batch: &Batch) -> Vec<u8> {
+    let len = batch
+        .iter()
+        .map(|(out, err)| 8 + out.len() + err.len())
+        .sum::<usize>();
+    let mut bytes = Vec::with_capacity(4 + len);
+    bytes.extend_from_slice(&PROTOCOL_VERSION.to_le_bytes());
+    for part in batch.iter().flat_map(|(out, err)| [out, err]) {
+        let part_len = u32::try_from(part.len()).expect("output segments are short");
+        bytes.extend_from_slice(&part_len.to_le_bytes());
+        bytes.extend_from_slice(part);
+    }
+    bytes
+}
+
+fn cut_short() -> ProtocolError {
+    ProtocolError::Malformed("output cut short".to_string())
+}
+
+fn take_u32(bytes: &mut &[u8]) -> Result<u32, ProtocolError> {
+    if bytes.len() < 4 {
+        return Err(cut_short());
+    }
+    let (head, rest) = bytes.split_at(4);
+    *bytes = rest;
+    Ok(u32::from_le_bytes([head[0], head[1], head[2], head[3]]))
+}
+
+fn take_part(bytes: &mut &[u8]) -> Result<Vec<u8>, ProtocolError> {
+    let len = take_u32(bytes)? as usize;
+    if bytes.len() < len {
+        return Err(cut_short());
+    }
+    let (part, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(part.to_vec())
+}
+
+/// Decodes output made by `encode_output`.
+///
+/// # Errors
+///
+/// Returns an error if the output is cut short or comes from another protocol version.
+pub fn decode_output(mut bytes: &[u8]) -> Result<Batch, ProtocolError> {
+    let version = take_u32(&mut bytes)?;
+    if version != PROTOCOL_VERSION {
+        return Err(ProtocolError::UnsupportedVersion(version));
+    }
+    let mut batch = vec![];
+    while !bytes.is_empty() {
+        let out = take_part(&mut bytes)?;
+        let err = take_part(&mut bytes)?;
+        batch.push((out, err));
+    }
+    Ok(batch)
+}
@@ -0,0 +1,237 @@
+use super::limits::{OutputLimiter, OutputLimits};
+use super::progress::Progress;
+use super::protocol::{Job, Request, Response};
+use super::transport::Transport;
+use crate::lang::Stats;
+use std::collections::VecDeque;
+use std::mem;
+
+/// How a job ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Finished,
+    /// The program stopped with an error, such as a syntax error
+    Failed(String),
+    /// The worker crashed or could not run the job
+    Error(String),
+    /// Aborted for writing more than the output limit
+    OutputLimit,
+    Cancelled,
+}
+
+/// The output of a job so far, and how it ended once it has.
+#[derive(Clone, Debug)]
+pub struct JobResult {
+    /// The output kept so far, byte for byte; it is only decoded for display
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub stats: Option<Stats>,
+    /// The last progress the worker reported
+    pub progress: Option<Progress>,
+    pub outcome: Option<Outcome>,
+    pub limiters: (OutputLimiter, OutputLimiter),
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+}
+
+impl JobResult {
+    fn new(limits: OutputLimits) -> Self {
+        Self {
+            stdout: vec![],
+            stderr: vec![],
+            stats: None,
+            progress: None,
+            outcome: None,
+            limiters: limits.limiters(),
+            start_time: None,
+            end_time: None,
+        }
+    }
+
+    /// Whether output was dropped because of the limits, in which case what is left can't be
+    /// checked against the expected output.
+    pub const fn is_truncated(&self) -> bool {
+        self.limiters.0.is_overflown() || self.limiters.1.is_overflown()
+    }
+
+    /// Seconds the job has run for, as of `now` if it is still running.
+    pub fn elapsed(&self, now: f64) -> f64 {
+        self.start_time
+            .map_or(0.0, |start| (self.end_time.unwrap_or(now) - start) / 1000.0)
+    }
+
+    /// Applies a response, returning the outcome if it ends the job.
+    fn apply(&mut self, response: Response) -> Option<Outcome> {
+        match response {
+            Response::Output(batch) => {
+                for (out, err) in batch {
+                    let stdout_ok = self.limiters.0.push(&mut self.stdout, &out);
+                    let stderr_ok = self.limiters.1.push(&mut self.stderr, &err);
+                    if !(stdout_ok && stderr_ok) {
+                        return Some(Outcome::OutputLimit);
+                    }
+                }
+                None
+            }
+            Response::Heartbeat(progress) => {
+                self.progress = Some(progress);
+                None
+            }
+            Response::Stats(stats) => {
+                self.stats = Some(stats);
+                None
+            }
+            Response::Finished => Some(Outcome::Finished),
+            Response::Failed(err) => Some(Outcome::Failed(err)),
+            Response::Error(err) => Some(Outcome::Error(err)),
+            Response::Ready => None,
+        }
+    }
+}
+
+/// Runs a batch of jobs on as many workers as it is given, up to a maximum.
+///
+/// Workers are requested with `request_workers` and handed over with `add_worker` as they
+/// become available; responses are picked up by `poll`. A worker whose job finishes normally
+/// goes on with the next job, while one that crashed or was aborted is terminated,
+/// and a replacement is requested if jobs are left.
+pub struct Scheduler<T> {
+    jobs: Vec<Job>,
+    results: Vec<JobResult>,
+    pending: VecDeque<usize>,
+    running: Vec<(usize, T)>,
+    idle: Vec<T>,
+    max_workers: usize,
+    // Workers requested but not handed over yet
+    requested: usize,
+}
+
+impl<T: Transport> Scheduler<T> {
+    pub fn new(jobs: Vec<Job>, limits: OutputLimits, max_workers: usize) -> Self {
+        Self {
+            results: vec![JobResult::new(limits); jobs.len()],
+            pending: (0..jobs.len()).collect(),
+            jobs,
+            running: vec![],
+            idle: vec![],
+            max_workers: max_workers.max(1),
+            requested: 0,
+        }
+    }
+
+    pub fn result(&self, job: usize) -> &JobResult {
+        &self.results[job]
+    }
+
+    /// Whether every job has ended.
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty() && self.running.is_empty()
+    }
+
+    /// How many more workers to request. They are counted as on their way
+    /// until they are passed to `add_worker` or `worker_failed`.
+    pub fn request_workers(&mut self) -> usize {
+        let wanted = (self.running.len() + self.pending.len()).min(self.max_workers);
+        let count = wanted.saturating_sub(self.running.len() + self.idle.len() + self.requested);
+        self.requested += count;
+        count
+    }
+
+    /// Starts the next job on `worker`. Returns the worker if no job is left for it.
+    pub fn add_worker(&mut self, worker: T, now: f64) -> Option<T> {
+        self.requested = self.requested.saturating_sub(1);
+        self.assign(worker, now)
+    }
+
+    /// Reports that a requested worker could not be started. If no other worker is left
+    /// to run them, the remaining jobs fail.
+    pub fn worker_failed(&mut self, now: f64) -> Vec<usize> {
+        self.requested = self.requested.saturating_sub(1);
+        if !self.running.is_empty() || self.requested > 0 {
+            return vec![];
+        }
+        let failed: Vec<_> = self.pending.drain(..).collect();
+        for &job in &failed {
+            self.end(job, Outcome::Error("no worker available".to_string()), now);
+        }
+        failed
+    }
+
+    fn assign(&mut self, mut worker: T, now: f64) -> Option<T> {
+        if let Some(job) = self.pending.pop_front() {
+            worker.send(Request::Run(self.jobs[job].clone()));
+            self.results[job].start_time = Some(now);
+            self.running.push((job, worker));
+            None
+        } else {
+            Some(worker)
+        }
+    }
+
+    /// Ends a job. The message of an error is added to its stderr, unless the worker
+    /// already wrote it there as the job's last output.
+    fn end(&mut self, job: usize, outcome: Outcome, now: f64) {
+        let result = &mut self.results[job];
+        if let Outcome::Failed(err) | Outcome::Error(err) = &outcome {
+            if !result.stderr.ends_with(err.as_bytes()) {
+                result.stderr.push(b'\n');
+                result.stderr.extend_from_slice(err.as_bytes());
+            }
+        }
+        result.outcome = Some(outcome);
+        result.end_time = Some(now);
+    }
+
+    /// Handles the responses that have arrived. Returns the jobs that ended.
+    pub fn poll(&mut self, now: f64) -> Vec<usize> {
+        let mut ended = vec![];
+        let mut i = 0;
+        while i < self.running.len() {
+            let (job, worker) = &mut self.running[i];
+            let result = &mut self.results[*job];
+            let mut outcome = None;
+            while let Some(response) = worker.try_recv() {
+                outcome = result.apply(response);
+                if outcome.is_some() {
+                    break;
+                }
+            }
+            if let Some(outcome) = outcome {
+                let (job, mut worker) = self.running.swap_remove(i);
+                // The worker is fine unless it crashed or went over the limits
+                let reusable = matches!(outcome, Outcome::Finished | Outcome::Failed(_));
+                self.end(job, outcome, now);
+                ended.push(job);
+                if reusable {
+                    if let Some(worker) = self.assign(worker, now) {
+                        self.idle.push(worker);
+                    }
+                } else {
+                    worker.terminate();
+                }
+            } else {
+                i += 1;
+            }
+        }
+        ended
+    }
+
+    /// Terminates the running jobs and drops the pending ones. Returns the jobs that ended.
+    pub fn cancel(&mut self, now: f64) -> Vec<usize> {
+        let mut cancelled: Vec<_> = self.pending.drain(..).collect();
+        for (job, mut worker) in mem::take(&mut self.running) {
+            worker.terminate();
+            cancelled.push(job);
+        }
+        cancelled.sort_unstable();
+        for &job in &cancelled {
+            self.end(job, Outcome::Cancelled, now);
+        }
+        cancelled
+    }
+
+    /// Takes the workers that are done and can run something else.
+    pub fn take_idle_workers(&mut self) -> Vec<T> {
+        mem::take(&mut self.idle)
+    }
+}
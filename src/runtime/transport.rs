@@ -0,0 +1,13 @@
+use super::protocol::{Request, Response};
+
+/// A connection to a worker that runs jobs, as seen from the side that hands them out.
+/// Web workers are one kind; tests use workers they can script.
+pub trait Transport {
+    fn send(&mut self, request: Request);
+
+    /// The next response from the worker, if one has arrived. Never blocks.
+    fn try_recv(&mut self) -> Option<Response>;
+
+    /// Stops the worker at once, whatever it is doing. It must not be used afterwards.
+    fn terminate(&mut self);
+}
@@ -1,35 +1,16 @@
 // rust-wasm porting of -- https://github.com/w3reality/async-thread-worker
 
 use super::prelude::*;
-use js_sys::{Array, Function, Object, Promise, Reflect};
+use crate::runtime::protocol::{self, Request, Response};
+use js_sys::{Array, Function, Promise, Reflect, Uint8Array};
 use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{MessageEvent, Worker, WorkerGlobalScope};
 
-fn atw_encode_result_msg(result: &JsValue, is_ok: bool, cont: bool) -> Object {
-    let msg = Object::new();
-    Reflect::set(msg.as_ref(), &JsValue::from("result"), result).unwrap();
-    Reflect::set(msg.as_ref(), &JsValue::from("isOk"), &JsValue::from(is_ok)).unwrap();
-    Reflect::set(msg.as_ref(), &JsValue::from("cont"), &JsValue::from(cont)).unwrap();
-    msg
-}
-
-fn atw_decode_result_msg(msg: &JsValue) -> (JsValue, bool, bool) {
-    let result = Reflect::get(msg, &JsValue::from("result")).unwrap_throw();
-    let is_ok = Reflect::get(msg, &JsValue::from("isOk"))
-        .unwrap_throw()
-        .as_bool()
-        .unwrap_throw();
-    let cont = Reflect::get(msg, &JsValue::from("cont"))
-        .unwrap_throw()
-        .as_bool()
-        .unwrap_throw();
-    (result, is_ok, cont)
-}
-
 // Bindings such as `post_message_with_transfer()` seem not available
 // in `web_sys::WorkerGlobalScope` (as opposed to `web_sys::Worker`).
 // So, we define and use a custom binding `JsWgs` instead.
@@ -61,16 +42,19 @@ impl ThreadWorker {
         }
     }
 
-    pub fn send_response(&self, payload: &JsValue, transfer: Option<&Array>, cont: bool) {
-        let default = Array::new();
-        let transfer = transfer.unwrap_or(&default);
-        self.wgs
-            .post_message_with_transfer(&atw_encode_result_msg(payload, true, cont), transfer);
-    }
-
-    pub fn send_error(&self, error: &JsValue) {
-        self.wgs
-            .post_message_with_transfer(&atw_encode_result_msg(error, false, false), &Array::new());
+    /// Sends a response to the UI. Output goes in a buffer that is transferred rather than
+    /// copied, and everything else as JSON.
+    pub fn send(&self, response: &Response) {
+        if let Response::Output(batch) = response {
+            let bytes = Uint8Array::from(&protocol::encode_output(batch)[..]);
+            self.wgs
+                .post_message_with_transfer(&bytes, &Array::of1(&bytes.buffer()));
+        } else {
+            self.wgs.post_message_with_transfer(
+                &JsValue::from(protocol::encode(response)),
+                &Array::new(),
+            );
+        }
     }
 
     pub fn set_callback_of(&self, target: &str, cb: &JsValue) {
@@ -87,8 +71,10 @@ pub struct Thread {
     worker: Worker,
     _on_message: Box<Closure<dyn FnMut(MessageEvent)>>,
     _on_error: Box<Closure<dyn FnMut(MessageEvent)>>,
+    // The promise of the initialization request, until it is settled
     resrej: Rc<RefCell<Option<(Function, Function)>>>,
-    messages: Rc<RefCell<(String, String)>>,
+    // Responses to `post()`, until they are picked up by `try_recv()`
+    responses: Rc<RefCell<VecDeque<Response>>>,
     is_terminated: RefCell<bool>,
 }
 
@@ -106,13 +92,10 @@ impl Thread {
         let worker = worker.unwrap_throw();
 
         let resrej = Rc::new(RefCell::new(None));
-        let messages = Rc::new(RefCell::new((
-            String::with_capacity(OUT_LIMIT),
-            String::with_capacity(OUT_LIMIT),
-        )));
-        let on_message = Self::create_onmessage(resrej.clone(), messages.clone());
+        let responses = Rc::new(RefCell::new(VecDeque::new()));
+        let on_message = Self::create_onmessage(resrej.clone(), responses.clone());
         worker.set_onmessage(Some(on_message.as_ref().unchecked_ref::<Function>()));
-        let on_error = Self::create_onerror(resrej.clone());
+        let on_error = Self::create_onerror(resrej.clone(), responses.clone());
         worker.set_onerror(Some(on_error.as_ref().unchecked_ref::<Function>()));
 
         Self {
@@ -120,14 +103,14 @@ impl Thread {
             _on_message: Box::new(on_message),
             _on_error: Box::new(on_error),
             resrej,
-            messages,
+            responses,
             is_terminated: RefCell::new(false),
         }
     }
 
     fn create_onmessage(
         resrej: Rc<RefCell<Option<(Function, Function)>>>,
-        messages: Rc<RefCell<(String, String)>>,
+        responses: Rc<RefCell<VecDeque<Response>>>,
     ) -> Closure<dyn FnMut(MessageEvent)> {
         Closure::wrap(Box::new(move |me: MessageEvent| {
             let msg = me.data();
@@ -137,59 +120,52 @@ impl Thread {
                 return;
             }
 
-            let (result, is_ok, cont) = atw_decode_result_msg(&msg);
-
-            if cont {
-                let (out, err) = JsValue::into_serde::<(String, String)>(&result).unwrap_throw();
-                {
-                    let out_collect = &mut messages.borrow_mut().0;
-                    if out_collect.len() + out.len() <= out_collect.capacity() {
-                        out_collect.push_str(&out);
-                        //console_ln!("out_collect: {}", out_collect);
-                    } else {
-                        out_collect.push_str(&out[..out_collect.capacity() - out_collect.len()]);
-                        let resrej_borrow = resrej.borrow();
-                        assert!(resrej_borrow.is_some());
-                        let (_res, rej) = resrej_borrow.as_ref().unwrap_throw();
-                        rej.call1(&JsValue::NULL, &JsValue::from("Stdout limit exceeded"))
-                            .unwrap();
-                    }
-                }
-                {
-                    let err_collect = &mut messages.borrow_mut().1;
-                    if err_collect.len() + err.len() <= err_collect.capacity() {
-                        err_collect.push_str(&err);
-                        //console_ln!("err_collect: {}", err_collect);
-                    } else {
-                        err_collect.push_str(&err[..err_collect.capacity() - err_collect.len()]);
-                        let resrej_borrow = resrej.borrow();
-                        assert!(resrej_borrow.is_some());
-                        let (_res, rej) = resrej_borrow.as_ref().unwrap_throw();
-                        rej.call1(&JsValue::NULL, &JsValue::from("Stderr limit exceeded"))
-                            .unwrap();
-                    }
+            let response = Self::decode(&msg);
+            // Only the first `Ready` answers the initialization request; later ones answer
+            // `post()` like on native workers
+            let init = match response {
+                Ok(Response::Ready) => resrej.borrow_mut().take(),
+                _ => None,
+            };
+            if let Some((res, _rej)) = init {
+                res.call1(&JsValue::NULL, &msg).unwrap_throw();
+                return;
+            }
+            match response {
+                Ok(response) => responses.borrow_mut().push_back(response),
+                Err(err) => {
+                    console_ln!("on_message(): {}", err);
+                    responses.borrow_mut().push_back(Response::Error(err));
                 }
-            } else {
-                let resrej_borrow = resrej.borrow();
-                assert!(resrej_borrow.is_some());
-                let (result, reject) = resrej_borrow.as_ref().unwrap_throw();
-                (if is_ok { result } else { reject })
-                    .call1(&JsValue::NULL, result)
-                    .unwrap_throw();
             }
         }) as Box<dyn FnMut(MessageEvent)>)
     }
 
+    /// Decodes a response, made of JSON or, for output, of bytes.
+    fn decode(msg: &JsValue) -> Result<Response, String> {
+        let response = if let Some(bytes) = msg.dyn_ref::<Uint8Array>() {
+            protocol::decode_output(&bytes.to_vec()).map(Response::Output)
+        } else if let Some(msg) = msg.as_string() {
+            protocol::decode(&msg)
+        } else {
+            return Err("expected a string or bytes".to_string());
+        };
+        response.map_err(|err| err.to_string())
+    }
+
     fn create_onerror(
         resrej: Rc<RefCell<Option<(Function, Function)>>>,
+        responses: Rc<RefCell<VecDeque<Response>>>,
     ) -> Closure<dyn FnMut(MessageEvent)> {
         Closure::wrap(Box::new(move |_me: MessageEvent| {
             console_ln!("terminated by error");
-            let resrej_borrow = resrej.borrow();
-            assert!(resrej_borrow.is_some());
-            let (_res, rej) = resrej_borrow.as_ref().unwrap_throw();
-            rej.call1(&JsValue::NULL, &JsValue::from("Thread: last req canceled"))
-                .unwrap();
+            responses
+                .borrow_mut()
+                .push_back(Response::Error("the worker crashed".to_string()));
+            if let Some((_res, rej)) = resrej.borrow_mut().take() {
+                rej.call1(&JsValue::NULL, &JsValue::from("Thread: last req canceled"))
+                    .unwrap();
+            }
         }) as Box<dyn FnMut(MessageEvent)>)
     }
 
@@ -206,8 +182,6 @@ impl Thread {
             }
 
             self.resrej.borrow_mut().insert((res, rej));
-            self.messages.borrow_mut().0.clear();
-            self.messages.borrow_mut().1.clear();
 
             let default = Array::new();
             let transfer = transfer.unwrap_or(&default);
@@ -219,12 +193,25 @@ impl Thread {
         JsFuture::from(promise).await
     }
 
+    /// Sends a request to the worker. Its responses are picked up with `try_recv()`.
+    pub fn post(&self, request: &Request) {
+        if *self.is_terminated.borrow() {
+            debug_ln!("Thread::post(): nop; already terminated");
+            return;
+        }
+        let payload = JsValue::from(protocol::encode(request));
+        self.worker.post_message(&payload).unwrap_throw();
+    }
+
+    pub fn try_recv(&self) -> Option<Response> {
+        self.responses.borrow_mut().pop_front()
+    }
+
     fn cancel_pending_requests(&self) {
-        let resrej_borrow = self.resrej.borrow();
-        assert!(resrej_borrow.is_some());
-        let (_res, rej) = resrej_borrow.as_ref().unwrap_throw();
-        rej.call1(&JsValue::NULL, &JsValue::from("Thread: last req canceled"))
-            .unwrap();
+        if let Some((_res, rej)) = self.resrej.borrow_mut().take() {
+            rej.call1(&JsValue::NULL, &JsValue::from("Thread: last req canceled"))
+                .unwrap();
+        }
     }
 
     pub fn terminate(&self) {
@@ -241,20 +228,6 @@ impl Thread {
     /* pub fn is_terminated(&self) -> bool {
         *self.is_terminated.borrow()
     } */
-
-    pub fn stdout(&self) -> String {
-        let out = &mut self.messages.borrow_mut().0;
-        let ret = out.clone();
-        out.clear();
-        ret
-    }
-
-    pub fn stderr(&self) -> String {
-        let err = &mut self.messages.borrow_mut().1;
-        let ret = err.clone();
-        err.clear();
-        ret
-    }
 }
 
 impl Drop for Thread {
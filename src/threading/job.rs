@@ -1,66 +1,31 @@
 use super::atw::ThreadWorker as AtwThreadWorker;
 use super::prelude::*;
-use crate::lang::{interpret, LangWriter};
-use std::rc::Rc;
-use wasm_bindgen::prelude::*;
-
-type ResultJJ = Result<JsValue, JsValue>;
-
-pub struct AtwThreadWriter {
-    atw_thw: Rc<AtwThreadWorker>,
-}
-
-impl AtwThreadWriter {
-    pub fn new(atw_thw: Rc<AtwThreadWorker>) -> Self {
-        Self { atw_thw }
+use crate::lang::LangWriter;
+use crate::runtime::job::{self, JobWriter, ResponseSink};
+use crate::runtime::panic;
+use crate::runtime::protocol::Response;
+use js_sys::Date;
+
+impl ResponseSink for AtwThreadWorker {
+    fn respond(&self, response: Response) {
+        self.send(&response);
     }
-}
-
-fn pass_encode(out: &str, err: &str) -> ResultJJ {
-    /* let (out, err) = (JsValue::from(out), JsValue::from(err));
-    let jsv = Array::of2(&out, &err).unchecked_into();
-    Ok(jsv) */
-    //Ok(JsValue::from_serde(&(out, err)).unwrap())
-    JsValue::from_serde(&(out, err)).map_err(|_| JsValue::from(err))
-}
 
-fn err_encode(err: &str) -> ResultJJ {
-    /* let (out, err) = (JsValue::from(out), JsValue::from(err));
-    let jsv = Array::of2(&out, &err).unchecked_into();
-    Ok(jsv) */
-    Err(JsValue::from(err))
-}
-
-impl LangWriter for AtwThreadWriter {
-    fn write_both(&mut self, out: &str, err: &str) {
-        send_result(&pass_encode(out, err), &self.atw_thw, true);
-    }
-    fn terminate(&mut self) {
-        send_result(&pass_encode("", ""), &self.atw_thw, false);
-    }
-    fn terminate_with_error(&mut self, msg: &str) {
-        self.write_err(msg);
-        send_result(&err_encode(msg), &self.atw_thw, false);
+    fn now(&self) -> f64 {
+        Date::now()
     }
 }
 
 pub fn init_thread_impls() {
-    AtwThreadWriter::init_impls();
-}
-
-pub fn send_result(result: &ResultJJ, atw_thw: &Rc<AtwThreadWorker>, cont: bool) {
-    match result {
-        // TODO !!!! optimise transferables cases
-        Ok(ref ret) => atw_thw.send_response(ret, None, cont),
-        Err(ref ret) => atw_thw.send_error(ret),
-    }
+    JobWriter::<AtwThreadWorker>::init_impls();
 }
 
-pub fn run_job_lang(jsv: &JsValue, atw_thw: Rc<AtwThreadWorker>) {
-    let (lang, pgm, input, args) = jsv
-        .into_serde::<(String, String, String, String)>()
-        .unwrap();
-    console_ln!("run_job_lang: {} {} {} {}", lang, pgm, input, args);
-    let mut writer = AtwThreadWriter::new(atw_thw);
-    interpret(&lang, &pgm, &input, &args, &mut writer);
+/// Reports panics of the running job like errors, with the panic message and location
+/// added to its stderr. Panics abort in wasm, so the worker can't run anything afterwards.
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let msg = panic::describe(info.payload(), info.location());
+        console_ln!("{}", msg);
+        job::report_crash(&msg);
+    }));
 }
@@ -1,17 +1,19 @@
 #![allow(clippy::future_not_send)]
 #![allow(clippy::needless_pass_by_value)]
-use js_sys::{ArrayBuffer, Object, Reflect};
+use js_sys::ArrayBuffer;
 use std::cell::RefCell;
 use wasm_bindgen::prelude::*;
 
 mod atw;
 mod job;
+mod pool;
 pub mod prelude;
 mod thread;
 pub mod utils;
 mod worker;
 //mod lang;
 
+pub use pool::WorkerPool;
 pub use thread::Thread;
 
 #[macro_export]
@@ -30,15 +32,6 @@ macro_rules! debug_ln {
     };
 }
 
-#[macro_export]
-macro_rules! exec_lang {
-    ($th:expr, $str1:expr, $str2:expr, $str3:expr, $str4:expr) => {
-        ($th).exec_lang($str1, $str2, $str3, $str4)
-    };
-}
-
-pub const OUT_LIMIT: usize = 131_072;
-
 pub struct WasmMt {
     pkg_js_uri: Option<String>,
     ab_init: RefCell<Option<ArrayBuffer>>,
@@ -123,21 +116,3 @@ impl WasmMt {
         Ok(Self::ab_init_from(&pkg_js))
     }
 }
-
-fn encode_task_msg(name: &str, data: Option<&JsValue>) -> Object {
-    let msg = Object::new();
-    Reflect::set(msg.as_ref(), &JsValue::from("task"), &JsValue::from(name)).unwrap();
-    if let Some(jsv) = data {
-        Reflect::set(msg.as_ref(), &JsValue::from("data"), jsv).unwrap();
-    }
-    msg
-}
-
-fn decode_task_msg(msg: &JsValue) -> (String, JsValue) {
-    let name = Reflect::get(msg, &JsValue::from("task"))
-        .unwrap_throw()
-        .as_string()
-        .unwrap_throw();
-    let jsv = Reflect::get(msg, &JsValue::from("data")).unwrap_throw();
-    (name, jsv)
-}
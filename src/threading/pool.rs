@@ -0,0 +1,73 @@
+use super::prelude::*;
+use super::Thread;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::spawn_local;
+
+/// Keeps initialized workers ready to run jobs. Instantiating the wasm module in a new worker
+/// takes far longer than most programs run, so it is done ahead of time in the background.
+///
+/// Only `size` workers are warmed up ahead of a run, but up to `capacity` of them are kept
+/// once given back, so that a run fanned out on many workers can reuse all of them.
+pub struct WorkerPool {
+    mt: WasmMt,
+    size: usize,
+    capacity: usize,
+    idle: RefCell<Vec<Thread>>,
+    // Workers that are still being initialized
+    warming: Cell<usize>,
+}
+
+impl WorkerPool {
+    pub fn new(mt: WasmMt, size: usize, capacity: usize) -> Rc<Self> {
+        let pool = Rc::new(Self {
+            mt,
+            size,
+            capacity: capacity.max(size),
+            idle: RefCell::new(vec![]),
+            warming: Cell::new(0),
+        });
+        pool.warm_up();
+        pool
+    }
+
+    /// Starts initializing workers until `size` of them are idle or on their way.
+    pub fn warm_up(self: &Rc<Self>) {
+        while self.idle.borrow().len() + self.warming.get() < self.size {
+            self.warming.set(self.warming.get() + 1);
+            let pool = Rc::clone(self);
+            spawn_local(async move {
+                let thread = pool.mt.thread().and_init().await;
+                pool.warming.set(pool.warming.get() - 1);
+                match thread {
+                    Ok(thread) => pool.idle.borrow_mut().push(thread),
+                    Err(err) => console_ln!("failed to initialize a worker: {:?}", err),
+                }
+            });
+        }
+    }
+
+    /// Hands out an idle worker, or initializes one right away if none is ready yet.
+    /// Either way, a replacement starts warming up.
+    pub async fn take(self: &Rc<Self>) -> Result<Thread, JsValue> {
+        let idle = self.idle.borrow_mut().pop();
+        let thread = match idle {
+            Some(thread) => Ok(thread),
+            None => self.mt.thread().and_init().await,
+        };
+        self.warm_up();
+        thread
+    }
+
+    /// Takes back a worker whose job has finished normally, so that it can run another one.
+    /// Workers that were terminated must not be given back; they are simply dropped.
+    pub fn give_back(&self, thread: Thread) {
+        let mut idle = self.idle.borrow_mut();
+        if idle.len() < self.capacity {
+            idle.push(thread);
+        } else {
+            debug_ln!("WorkerPool::give_back(): pool is full; dropping the worker");
+        }
+    }
+}
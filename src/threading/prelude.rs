@@ -1,2 +1,2 @@
-pub use super::{job::init_thread_impls, WasmMt, OUT_LIMIT};
-pub use crate::{console_ln, debug_ln, exec_lang};
+pub use super::{job::init_thread_impls, WasmMt};
+pub use crate::{console_ln, debug_ln};
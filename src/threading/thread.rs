@@ -1,13 +1,12 @@
 use super::atw::Thread as AtwThread;
-use super::encode_task_msg;
 use super::prelude::*;
+use crate::runtime::protocol::{Request, Response};
+use crate::runtime::transport::Transport;
 use js_sys::{Array, ArrayBuffer, Object, Reflect};
 use std::cell::RefCell;
 use wasm_bindgen::prelude::*;
 use web_sys::{Blob, BlobPropertyBag, Url};
 
-type ResultJJ = Result<JsValue, JsValue>;
-
 pub struct Thread {
     ab_init: RefCell<Option<ArrayBuffer>>,
     ab_wasm: RefCell<Option<ArrayBuffer>>,
@@ -104,25 +103,21 @@ impl Thread {
         Ok(self)
     }
 
-    pub async fn exec_lang(&self, lang: &str, pgm: &str, input: &str, args: &str) -> ResultJJ {
-        let data = JsValue::from_serde(&(lang, pgm, input, args)).unwrap();
-        let msg = encode_task_msg("job-lang", Some(&data));
-        self.atw_th.send_request(&msg, None).await
-    }
-
-    pub fn terminate(&self) {
-        self.atw_th.terminate();
-    }
-
     /* pub fn is_terminated(&self) -> bool {
         self.atw_th.is_terminated()
     } */
+}
 
-    pub fn stdout(&self) -> String {
-        self.atw_th.stdout()
+impl Transport for Thread {
+    fn send(&mut self, request: Request) {
+        self.atw_th.post(&request);
     }
 
-    pub fn stderr(&self) -> String {
-        self.atw_th.stderr()
+    fn try_recv(&mut self) -> Option<Response> {
+        self.atw_th.try_recv()
+    }
+
+    fn terminate(&mut self) {
+        self.atw_th.terminate();
     }
 }
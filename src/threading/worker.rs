@@ -1,7 +1,9 @@
 use super::atw::ThreadWorker as AtwThreadWorker;
-use super::decode_task_msg;
 use super::job;
 use super::prelude::*;
+use crate::runtime;
+use crate::runtime::protocol::{self, Request, Response};
+use std::cell::RefCell;
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use web_sys::{MessageEvent, WorkerGlobalScope};
@@ -9,10 +11,9 @@ use web_sys::{MessageEvent, WorkerGlobalScope};
 #[allow(dead_code)]
 #[wasm_bindgen]
 pub fn wmt_bootstrap(wgs: WorkerGlobalScope) -> _WorkerInner {
+    job::install_panic_hook();
     let worker = _WorkerInner::new(wgs);
-    worker
-        .atw_thw
-        .send_response(&JsValue::from("bootstrap COMPLETE"), None, false);
+    worker.atw_thw.send(&Response::Ready);
 
     worker
 }
@@ -38,22 +39,32 @@ impl _WorkerInner {
     }
 
     fn create_onmessage(atw_thw: Rc<AtwThreadWorker>) -> Closure<dyn FnMut(MessageEvent)> {
+        // Input provided ahead of the next job
+        let pending_input = Rc::new(RefCell::new(vec![]));
         Closure::wrap(Box::new(move |me: MessageEvent| {
             let data = &me.data();
-            Self::on_request_inner(atw_thw.clone(), data);
+            Self::on_request_inner(&atw_thw, &pending_input, data);
         }) as Box<dyn FnMut(MessageEvent)>)
     }
 
-    fn on_request_inner(atw_thw: Rc<AtwThreadWorker>, task_msg: &JsValue) {
-        let (ref name, ref jsv) = decode_task_msg(task_msg);
-        debug_ln!("on_request_inner(): task: {}", name);
+    fn on_request_inner(
+        atw_thw: &Rc<AtwThreadWorker>,
+        pending_input: &RefCell<Vec<u8>>,
+        msg: &JsValue,
+    ) {
+        let request = msg.as_string().map_or_else(
+            || {
+                Err(protocol::ProtocolError::Malformed(
+                    "expected a string".to_string(),
+                ))
+            },
+            |msg| protocol::decode::<Request>(&msg),
+        );
+        debug_ln!("on_request_inner(): request: {:?}", request);
 
-        if name == "job-lang" {
-            job::run_job_lang(jsv, atw_thw);
-        } else {
-            let msg = format!("unknown task: {}", name);
-            console_ln!("err: {}", &msg);
-            panic!("{}", msg);
+        if let Err(err) = &request {
+            console_ln!("err: {}", err);
         }
+        runtime::job::handle_request(atw_thw, pending_input, request);
     }
 }
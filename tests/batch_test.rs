@@ -0,0 +1,70 @@
+use try_in_browser::runtime::batch::{OutputBatcher, CHECK_WRITES, FLUSH_BYTES, FLUSH_INTERVAL_MS};
+
+#[test]
+fn test_coalesce() {
+    let mut batcher = OutputBatcher::new();
+    // The first write goes out at once
+    assert_eq!(
+        batcher.push(b"a", b"", || 0.0),
+        Some(vec![(b"a".to_vec(), vec![])])
+    );
+    assert_eq!(batcher.push(b"b", b"", || 1.0), None);
+    assert_eq!(batcher.push(b"c", b"", || 2.0), None);
+    assert_eq!(batcher.push(b"", b"x", || 3.0), None);
+    assert_eq!(batcher.push(b"", b"y", || 4.0), None);
+    assert_eq!(batcher.push(b"d", b"z", || 5.0), None);
+    assert_eq!(
+        batcher.flush(6.0),
+        Some(vec![
+            (b"bc".to_vec(), b"xy".to_vec()),
+            (b"d".to_vec(), b"z".to_vec()),
+        ])
+    );
+    assert_eq!(batcher.flush(7.0), None);
+}
+
+#[test]
+fn test_due() {
+    let mut batcher = OutputBatcher::new();
+    assert!(batcher.push(b"a", b"", || 0.0).is_some());
+    assert!(batcher
+        .push(b"b", b"", || FLUSH_INTERVAL_MS / 2.0)
+        .is_none());
+    // The clock isn't read again for a while
+    for _ in 1..CHECK_WRITES {
+        assert!(batcher.push(b"c", b"", || panic!("clock read")).is_none());
+    }
+    assert_eq!(
+        batcher.push(b"d", b"", || FLUSH_INTERVAL_MS),
+        Some(vec![(
+            format!("b{}d", "c".repeat(CHECK_WRITES - 1)).into_bytes(),
+            vec![]
+        )])
+    );
+
+    let big = vec![b'x'; FLUSH_BYTES];
+    assert!(batcher.push(b"", b"", || FLUSH_INTERVAL_MS + 1.0).is_none());
+    assert_eq!(
+        batcher.push(&big, b"", || FLUSH_INTERVAL_MS + 1.0),
+        Some(vec![(big, vec![])])
+    );
+}
+
+#[test]
+fn test_poll() {
+    let mut batcher = OutputBatcher::new();
+    assert!(batcher.push(b"a", b"", || 0.0).is_some());
+    assert!(batcher.push(b"b", b"", || 1.0).is_none());
+    // The held back write goes out once due, even if nothing else is written
+    assert_eq!(batcher.poll(FLUSH_INTERVAL_MS / 2.0), None);
+    assert_eq!(
+        batcher.poll(FLUSH_INTERVAL_MS),
+        Some(vec![(b"b".to_vec(), vec![])])
+    );
+    assert_eq!(batcher.poll(FLUSH_INTERVAL_MS * 3.0), None);
+    // So does the next write after a quiet period
+    assert_eq!(
+        batcher.push(b"c", b"", || FLUSH_INTERVAL_MS * 3.0),
+        Some(vec![(b"c".to_vec(), vec![])])
+    );
+}
@@ -0,0 +1,65 @@
+use try_in_browser::runtime::controller::{RunController, RunState};
+
+#[test]
+fn test_run() {
+    let mut controller = RunController::new();
+    assert_eq!(controller.state(), RunState::Idle);
+    let run = controller.start().unwrap();
+    assert_eq!(controller.state(), RunState::Starting);
+    assert_eq!(controller.start(), None);
+    assert!(controller.started(run));
+    assert_eq!(controller.state(), RunState::Running);
+    assert_eq!(controller.start(), None);
+    // More workers may join a run
+    assert!(controller.started(run));
+    assert!(controller.finished(run));
+    assert_eq!(controller.state(), RunState::Idle);
+    assert!(!controller.finished(run));
+    assert!(!controller.started(run));
+
+    let next = controller.start().unwrap();
+    assert_ne!(next, run);
+}
+
+#[test]
+fn test_stop() {
+    let mut controller = RunController::new();
+    assert!(!controller.stop());
+    assert_eq!(controller.state(), RunState::Idle);
+
+    // Stopping while running ends the run at once, and its end is then ignored
+    let run = controller.start().unwrap();
+    assert!(controller.started(run));
+    assert!(controller.stop());
+    assert_eq!(controller.state(), RunState::Idle);
+    assert!(!controller.stop());
+    assert!(!controller.finished(run));
+    assert_eq!(controller.state(), RunState::Idle);
+
+    // Stopping while starting waits for the worker, which is then not used
+    let run = controller.start().unwrap();
+    assert!(controller.stop());
+    assert_eq!(controller.state(), RunState::Stopping);
+    assert!(!controller.stop());
+    assert_eq!(controller.start(), None);
+    assert!(!controller.started(run));
+    assert_eq!(controller.state(), RunState::Idle);
+    assert!(!controller.finished(run));
+}
+
+#[test]
+fn test_stale() {
+    let mut controller = RunController::new();
+    let old = controller.start().unwrap();
+    assert!(controller.started(old));
+    assert!(controller.stop());
+
+    // News about the stopped run doesn't affect the next one
+    let run = controller.start().unwrap();
+    assert!(!controller.started(old));
+    assert_eq!(controller.state(), RunState::Starting);
+    assert!(controller.started(run));
+    assert!(!controller.finished(old));
+    assert_eq!(controller.state(), RunState::Running);
+    assert!(controller.finished(run));
+}
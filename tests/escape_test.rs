@@ -0,0 +1,23 @@
+use try_in_browser::escape::{from_text, needs_escapes, to_text};
+
+#[test]
+fn test_plain() {
+    assert!(!needs_escapes(b"/a\\/b/"));
+    assert_eq!(to_text(b"/a\\/b/", false), "/a\\/b/");
+    assert_eq!(from_text("/a\\/b/\\xff", false), b"/a\\/b/\\xff");
+}
+
+#[test]
+fn test_escaped() {
+    let bytes = b"\\a\xff\xc3\xa9\xe2\x82\0";
+    assert!(needs_escapes(bytes));
+    assert_eq!(to_text(bytes, true), "\\\\a\\xff\u{e9}\\xe2\\x82\0");
+    assert_eq!(from_text(&to_text(bytes, true), true), bytes);
+    assert_eq!(to_text(bytes, false), "\\a\u{fffd}\u{e9}\u{fffd}\0");
+}
+
+#[test]
+fn test_lenient() {
+    assert_eq!(from_text("\\xFf\\x4", true), b"\xff\\x4");
+    assert_eq!(from_text("\\q\\x+f\\", true), b"\\q\\x+f\\");
+}
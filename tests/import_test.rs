@@ -0,0 +1,90 @@
+use data_encoding::{BASE64, BASE64URL_NOPAD};
+use miniz_oxide::deflate::compress_to_vec;
+use std::convert::TryFrom;
+use try_in_browser::import::{import_url, ImportError};
+use try_in_browser::lang::LangWriter;
+use try_in_browser::permalink::Permalink;
+
+struct NullWriter;
+
+impl LangWriter for NullWriter {
+    fn write_both(&mut self, _out: &[u8], _err: &[u8]) {}
+}
+
+fn tio_link(state: &[u8]) -> String {
+    let data = BASE64.encode(&compress_to_vec(state, 9));
+    format!(
+        "https://tio.run/##{}",
+        data.replace('+', "@").trim_end_matches('=')
+    )
+}
+
+fn ato_link(state: &[u8]) -> String {
+    let data = BASE64URL_NOPAD.encode(&compress_to_vec(state, 9));
+    format!("https://ato.pxeger.com/run?1={}", data)
+}
+
+fn msgpack_str(buf: &mut Vec<u8>, s: &str) {
+    buf.push(0xd9);
+    buf.push(u8::try_from(s.len()).unwrap());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+#[test]
+fn test_tio() {
+    NullWriter::init_impls();
+    let state = b"brainfuck\xff\xff,[.,]\xff\xffHello!\xfe\xfe-o\xffx";
+    assert_eq!(
+        import_url(&tio_link(state)),
+        Ok(Permalink::new("brainfuck", ",[.,]", "Hello!", "-o x"))
+    );
+    let state = b"deadfish-\xffi\xffiiso\xffo";
+    assert_eq!(
+        import_url(&tio_link(state)),
+        Ok(Permalink {
+            header: "i".to_string(),
+            footer: "o".to_string(),
+            ..Permalink::new("Deadfish", "iiso", "", "")
+        })
+    );
+    assert_eq!(
+        import_url("tio.run/#slashes"),
+        Ok(Permalink::new("///", "", "", ""))
+    );
+}
+
+#[test]
+fn test_ato() {
+    NullWriter::init_impls();
+    let mut state = vec![0x84];
+    msgpack_str(&mut state, "language");
+    msgpack_str(&mut state, "brainfuck");
+    msgpack_str(&mut state, "code");
+    msgpack_str(&mut state, "LFsuLF0=");
+    msgpack_str(&mut state, "codeEncoding");
+    msgpack_str(&mut state, "base64");
+    msgpack_str(&mut state, "arguments");
+    state.push(0x91);
+    msgpack_str(&mut state, "-h");
+    assert_eq!(
+        import_url(&ato_link(&state)),
+        Ok(Permalink::new("brainfuck", ",[.,]", "", "-h"))
+    );
+}
+
+#[test]
+fn test_errors() {
+    NullWriter::init_impls();
+    assert_eq!(
+        import_url("https://example.com/#abc"),
+        Err(ImportError::UnsupportedUrl)
+    );
+    assert_eq!(
+        import_url("https://tio.run/##!!!"),
+        Err(ImportError::InvalidData)
+    );
+    assert_eq!(
+        import_url(&tio_link(b"python3\xff\xffprint(1)")),
+        Err(ImportError::UnknownLanguage("python3".to_string()))
+    );
+}
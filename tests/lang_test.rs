@@ -1,10 +1,12 @@
 use indoc::indoc;
-use std::io::Write;
-use try_in_browser::lang::{interpret, LangWriter};
+use try_in_browser::lang::{
+    get_lang_names, get_version, has_version, interpret, interpret_version, LangWriter, Stats,
+};
 
 struct VecWriter {
     stdout: Vec<u8>,
     stderr: Vec<u8>,
+    stats: Option<Stats>,
 }
 
 impl VecWriter {
@@ -12,6 +14,7 @@ impl VecWriter {
         Self {
             stdout: vec![],
             stderr: vec![],
+            stats: None,
         }
     }
 
@@ -21,9 +24,12 @@ impl VecWriter {
 }
 
 impl LangWriter for VecWriter {
-    fn write_both(&mut self, out: &str, err: &str) {
-        write!(self.stdout, "{}", out).unwrap();
-        write!(self.stderr, "{}", err).unwrap();
+    fn write_both(&mut self, out: &[u8], err: &[u8]) {
+        self.stdout.extend_from_slice(out);
+        self.stderr.extend_from_slice(err);
+    }
+    fn write_stats(&mut self, stats: &Stats) {
+        self.stats = Some(stats.clone());
     }
 }
 
@@ -129,3 +135,61 @@ fn test_slashes() {
         assert_eq!(err, b"");
     }
 }
+
+#[test]
+fn test_binary() {
+    VecWriter::init_impls();
+    let mut writer = VecWriter::new();
+    interpret("brainfuck", ",.,.", [0xff, 0x80], "", &mut writer);
+    assert_eq!(writer.raw().0, [0xff, 0x80]);
+
+    let mut writer = VecWriter::new();
+    interpret("///", "/\u{e9}/\u{fc}/\u{e9}\u{e9}", "", "", &mut writer);
+    assert_eq!(writer.raw().0, "\u{fc}\u{fc}".as_bytes());
+
+    let mut writer = VecWriter::new();
+    interpret("///", b"/\xff/b/\xffa\xff", "", "", &mut writer);
+    assert_eq!(writer.raw().0, b"bab");
+}
+
+#[test]
+fn test_stats() {
+    VecWriter::init_impls();
+    let mut writer = VecWriter::new();
+    let pgm = "+[->>+[-]<<]>.";
+    interpret("brainfuck", pgm, "", "", &mut writer);
+    assert_eq!(
+        writer.stats,
+        Some(Stats {
+            steps: 14,
+            peak_tape: Some(3),
+            max_loop_depth: Some(2),
+            substitutions: None,
+        })
+    );
+
+    let mut writer = VecWriter::new();
+    interpret("Deadfish", "iisso", "", "", &mut writer);
+    assert_eq!(writer.stats.map(|s| s.steps), Some(5));
+
+    let mut writer = VecWriter::new();
+    interpret("///", "/a/bb//b/c/aa", "", "", &mut writer);
+    let (out, _err) = writer.raw();
+    assert_eq!(out, b"cccc");
+    assert_eq!(writer.stats.and_then(|s| s.substitutions), Some(6));
+}
+
+#[test]
+fn test_versions() {
+    VecWriter::init_impls();
+    for &lang in get_lang_names() {
+        let version = get_version(lang).unwrap();
+        assert!(has_version(lang, version));
+    }
+    assert!(!has_version("brainfuck", "0.0.0"));
+
+    // Unavailable versions fall back to the current one
+    let mut writer = VecWriter::new();
+    interpret_version("brainfuck", Some("0.0.0"), ",[.,]", "hi", "", &mut writer);
+    assert_eq!(writer.raw(), (&b"hi"[..], &b""[..]));
+}
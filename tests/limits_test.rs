@@ -0,0 +1,76 @@
+use try_in_browser::runtime::limits::{OutputLimiter, OutputLimits, OverflowPolicy};
+
+fn run(policy: OverflowPolicy, chunks: &[&str]) -> (Vec<u8>, bool, OutputLimiter) {
+    let mut limiter = OutputLimiter::new(5, policy);
+    let mut dest = vec![];
+    let mut keep_running = true;
+    for chunk in chunks {
+        keep_running &= limiter.push(&mut dest, chunk.as_bytes());
+    }
+    (dest, keep_running, limiter)
+}
+
+#[test]
+fn test_within_limit() {
+    for &policy in &OverflowPolicy::ALL {
+        let (dest, keep_running, limiter) = run(policy, &["ab", "cde"]);
+        assert_eq!(dest, b"abcde");
+        assert!(keep_running);
+        assert!(!limiter.is_overflown());
+        assert_eq!(limiter.note("stdout"), None);
+    }
+}
+
+#[test]
+fn test_policies() {
+    let chunks = ["abc", "def", "ghi"];
+    assert_eq!(run(OverflowPolicy::Abort, &chunks[..2]).0, b"abcde");
+    assert!(!run(OverflowPolicy::Abort, &chunks).1);
+
+    let (dest, keep_running, limiter) = run(OverflowPolicy::KeepHead, &chunks);
+    assert_eq!(dest, b"abcde");
+    assert!(keep_running);
+    assert_eq!(
+        limiter.note("stdout").unwrap(),
+        "stdout exceeded the limit of 5 bytes (9 bytes written); only the first part is shown"
+    );
+
+    let (dest, keep_running, _) = run(OverflowPolicy::KeepTail, &chunks);
+    assert_eq!(dest, b"efghi");
+    assert!(keep_running);
+
+    let (dest, keep_running, limiter) = run(OverflowPolicy::Discard, &chunks);
+    assert_eq!(dest, b"");
+    assert!(keep_running);
+    assert!(limiter.is_overflown());
+}
+
+#[test]
+fn test_char_boundaries() {
+    // Multi-byte characters are never split
+    assert_eq!(run(OverflowPolicy::KeepHead, &["abcd\u{e9}"]).0, b"abcd");
+    assert_eq!(run(OverflowPolicy::KeepTail, &["\u{e9}abcd"]).0, b"abcd");
+    assert_eq!(
+        run(OverflowPolicy::KeepTail, &["a\u{e9}bcd"]).0,
+        "\u{e9}bcd".as_bytes()
+    );
+    // Bytes that are not UTF-8 are cut at the limit
+    let mut dest = vec![];
+    OutputLimiter::new(5, OverflowPolicy::KeepHead).push(&mut dest, &[0xff; 9]);
+    assert_eq!(dest, [0xff; 5]);
+}
+
+#[test]
+fn test_names() {
+    for &policy in &OverflowPolicy::ALL {
+        assert_eq!(OverflowPolicy::from_name(policy.name()), Some(policy));
+    }
+    assert_eq!(OverflowPolicy::from_name("truncate"), None);
+    let limits = OutputLimits {
+        stderr: 1,
+        ..OutputLimits::default()
+    };
+    let (stdout, mut stderr) = limits.limiters();
+    assert!(!stderr.push(&mut vec![], b"ab"));
+    assert!(!stdout.is_overflown());
+}
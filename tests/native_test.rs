@@ -0,0 +1,198 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use try_in_browser::runtime::limits::{OutputLimits, OverflowPolicy};
+use try_in_browser::runtime::native::NativeWorker;
+use try_in_browser::runtime::protocol::{Job, Request, Response};
+use try_in_browser::runtime::scheduler::{Outcome, Scheduler};
+use try_in_browser::runtime::transport::Transport;
+
+fn job(lang: &str, code: &str, input: &str) -> Job {
+    Job {
+        lang: lang.to_string(),
+        code: code.as_bytes().to_vec(),
+        input: input.as_bytes().to_vec(),
+        ..Job::default()
+    }
+}
+
+/// Writes "S" 40 times.
+const SSS: &str = "+++++++++[>+++++++++<-]>++>++++++++[<.....>-]";
+
+fn now(start_time: Instant) -> f64 {
+    start_time.elapsed().as_secs_f64() * 1000.0
+}
+
+/// Runs the jobs on native workers until every one of them has ended.
+fn run(jobs: Vec<Job>, limits: OutputLimits) -> Scheduler<NativeWorker> {
+    let start_time = Instant::now();
+    let mut scheduler = Scheduler::new(jobs, limits, 2);
+    while !scheduler.is_done() {
+        for _ in 0..scheduler.request_workers() {
+            let _res = scheduler.add_worker(NativeWorker::spawn(), now(start_time));
+        }
+        scheduler.poll(now(start_time));
+        thread::sleep(Duration::from_millis(1));
+    }
+    scheduler
+}
+
+/// Waits for the next response, failing the test if none comes.
+fn recv(worker: &mut NativeWorker) -> Response {
+    let start_time = Instant::now();
+    while start_time.elapsed() < Duration::from_secs(60) {
+        if let Some(response) = worker.try_recv() {
+            return response;
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    panic!("no response from the worker");
+}
+
+#[test]
+fn test_streaming() {
+    let jobs = vec![
+        job("brainfuck", SSS, ""),
+        job("brainfuck", ",[.,]", "cat"),
+        job("brainfuck", ",[.,]", "dog"),
+        job("brainfuck", "-.", ""),
+    ];
+    let scheduler = run(jobs, OutputLimits::default());
+    let result = scheduler.result(0);
+    assert_eq!(result.outcome, Some(Outcome::Finished));
+    assert_eq!(result.stdout, "S".repeat(40).as_bytes());
+    assert_eq!(result.stderr, b"");
+    assert_eq!(scheduler.result(1).stdout, b"cat");
+    assert_eq!(scheduler.result(2).stdout, b"dog");
+    assert_eq!(scheduler.result(2).outcome, Some(Outcome::Finished));
+    // Output that is not UTF-8 is kept byte for byte
+    assert_eq!(scheduler.result(3).stdout, b"\xff");
+
+    // Input provided ahead of a job comes before its own
+    let mut worker = NativeWorker::spawn();
+    worker.send(Request::ProvideInput(b"hot ".to_vec()));
+    assert_eq!(recv(&mut worker), Response::Ready);
+    worker.send(Request::Run(job("brainfuck", ",[.,]", "dog")));
+    let mut stdout = vec![];
+    loop {
+        match recv(&mut worker) {
+            Response::Output(batch) => {
+                for (out, _) in batch {
+                    stdout.extend(out);
+                }
+            }
+            Response::Stats(_) => {}
+            response => {
+                assert_eq!(response, Response::Finished);
+                break;
+            }
+        }
+    }
+    assert_eq!(stdout, b"hot dog");
+}
+
+#[test]
+fn test_limits() {
+    let limits = OutputLimits {
+        stdout: 1000,
+        stderr: 1000,
+        policy: OverflowPolicy::Abort,
+    };
+    let jobs = vec![job("brainfuck", "+[.]", ""), job("brainfuck", SSS, "")];
+    let scheduler = run(jobs, limits);
+    let result = scheduler.result(0);
+    assert_eq!(result.outcome, Some(Outcome::OutputLimit));
+    assert_eq!(result.stdout, [1; 1000]);
+    assert_eq!(scheduler.result(1).outcome, Some(Outcome::Finished));
+}
+
+#[test]
+fn test_terminate() {
+    let mut worker = NativeWorker::spawn();
+    worker.send(Request::Run(job("brainfuck", "+[.]", "")));
+    assert!(matches!(recv(&mut worker), Response::Output(_)));
+    worker.terminate();
+    // The job stops at its next write, without a crash being reported
+    worker.join();
+
+    // An idle worker stops at once
+    let mut worker = NativeWorker::spawn();
+    worker.terminate();
+    assert_eq!(worker.try_recv(), None);
+    worker.join();
+}
+
+#[test]
+fn test_heartbeat() {
+    let mut worker = NativeWorker::spawn();
+    worker.send(Request::Run(job("brainfuck", "+[]", "")));
+    let progress = match recv(&mut worker) {
+        Response::Heartbeat(progress) => progress,
+        response => panic!("unexpected response: {:?}", response),
+    };
+    assert!(progress.steps > 0);
+    assert!(matches!(progress.ip, Some(1 | 2)));
+    let next = match recv(&mut worker) {
+        Response::Heartbeat(progress) => progress,
+        response => panic!("unexpected response: {:?}", response),
+    };
+    assert!(next.steps > progress.steps);
+    // A silent job stops at its next heartbeat check
+    worker.terminate();
+    worker.join();
+
+    // Output held back by batching is sent ahead of the heartbeat
+    let mut worker = NativeWorker::spawn();
+    worker.send(Request::Run(job("brainfuck", "+.[.]", "")));
+    let mut stdout = 0;
+    loop {
+        match recv(&mut worker) {
+            Response::Output(batch) => {
+                stdout += batch.iter().map(|(out, _)| out.len()).sum::<usize>();
+            }
+            Response::Heartbeat(progress) => {
+                assert_eq!(progress.stdout, stdout);
+                break;
+            }
+            response => panic!("unexpected response: {:?}", response),
+        }
+    }
+}
+
+#[test]
+fn test_program_error() {
+    // A program error is no crash, and the worker runs the next job
+    let mut worker = NativeWorker::spawn();
+    worker.send(Request::Run(job("brainfuck", "]", "")));
+    let err = loop {
+        match recv(&mut worker) {
+            Response::Output(_) => {}
+            Response::Failed(err) => break err,
+            response => panic!("unexpected response: {:?}", response),
+        }
+    };
+    assert!(err.starts_with("Extra `]`"));
+    worker.send(Request::Init);
+    assert_eq!(recv(&mut worker), Response::Ready);
+}
+
+// No built-in language panics, so this needs ExampleLang, which is only built in for debugging
+#[cfg(feature = "ui_debug")]
+#[test]
+fn test_crash() {
+    let jobs = vec![
+        job("ExampleLang", "crasher", ""),
+        job("brainfuck", ",[.,]", "cat"),
+    ];
+    let scheduler = run(jobs, OutputLimits::default());
+    let result = scheduler.result(0);
+    let err = match &result.outcome {
+        Some(Outcome::Error(err)) => err,
+        outcome => panic!("unexpected outcome: {:?}", outcome),
+    };
+    assert!(err.starts_with("panicked at 'wtf', "));
+    assert!(err.contains("example_lang.rs"));
+    assert!(result.stderr.ends_with(format!("\n{}", err).as_bytes()));
+    assert_eq!(result.stdout, "S".repeat(40).as_bytes());
+    // Other workers go on
+    assert_eq!(scheduler.result(1).stdout, b"cat");
+}
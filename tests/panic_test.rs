@@ -0,0 +1,19 @@
+use std::panic::Location;
+use try_in_browser::runtime::panic::describe;
+
+#[test]
+fn test_describe() {
+    let location = Location::caller();
+    let expected = format!(
+        "panicked at 'index out of bounds', {}:{}:{}",
+        location.file(),
+        location.line(),
+        location.column()
+    );
+    assert_eq!(describe(&"index out of bounds", Some(location)), expected);
+    assert_eq!(
+        describe(&"index out of bounds".to_string(), Some(location)),
+        expected
+    );
+    assert_eq!(describe(&42, None), "panicked at 'Box<dyn Any>'");
+}
@@ -0,0 +1,198 @@
+use data_encoding::BASE64URL_NOPAD;
+use try_in_browser::permalink::{DecodeError, Permalink, TestCase, SITE_URL, VERSION_MARKER};
+
+#[test]
+fn test_roundtrip() {
+    let permalinks = [
+        Permalink::default(),
+        Permalink::new("brainfuck", ",[.,]", "Hello!", ""),
+        Permalink::new("///", "/a/b/".repeat(1000), "", "-h"),
+        Permalink::new("Deadfish", "iiso\u{1f41f}", "\0\n", "-o"),
+        Permalink {
+            header: "ii".to_string(),
+            footer: "o".to_string(),
+            ..Permalink::new("Deadfish", "s", "", "")
+        },
+        Permalink {
+            lang_version: "1.0.0".to_string(),
+            tests: vec![
+                TestCase::new("a", ""),
+                TestCase {
+                    expected: Some(String::new()),
+                    ..TestCase::new("", "-h")
+                },
+                TestCase {
+                    expected: Some("=b\n".to_string()),
+                    ..TestCase::new("b", "")
+                },
+            ],
+            ..Permalink::new("brainfuck", ",[.,]", "", "")
+        },
+    ];
+    for permalink in &permalinks {
+        let hash_path = permalink.encode();
+        assert_eq!(hash_path[0], VERSION_MARKER);
+        assert_eq!(Permalink::decode(&hash_path).as_ref(), Ok(permalink));
+    }
+}
+
+#[test]
+fn test_binary() {
+    let permalink = Permalink::new("brainfuck", b",[.,]\xff\xfe", [0x80, 0, 0xc3], "");
+    assert_eq!(
+        Permalink::decode(&permalink.encode()).as_ref(),
+        Ok(&permalink)
+    );
+    assert_eq!(permalink.full_code(), b",[.,]\xff\xfe");
+    let legacy = ["@YnJhaW5mdWNr", "@_w", "@gA"];
+    assert_eq!(
+        Permalink::decode(&legacy),
+        Ok(Permalink::new("brainfuck", [0xff], [0x80], ""))
+    );
+}
+
+#[test]
+fn test_compression() {
+    let permalink = Permalink::new("///", "/a/b/".repeat(1000), "", "");
+    let hash_path = permalink.encode();
+    assert!(hash_path[1].len() < 100);
+}
+
+#[test]
+fn test_legacy() {
+    let hash_path = ["@YnJhaW5mdWNr", "@LFsuLF0", "@aGk", "@"];
+    assert_eq!(
+        Permalink::decode(&hash_path),
+        Ok(Permalink::new("brainfuck", ",[.,]", "hi", ""))
+    );
+    assert_eq!(
+        Permalink::decode(&["@YnJhaW5mdWNr"]),
+        Ok(Permalink::new("brainfuck", "", "", ""))
+    );
+    assert_eq!(
+        Permalink::decode(&["@YnJhaW5mdWNr", "@!!"]),
+        Err(DecodeError::InvalidBase64)
+    );
+}
+
+#[test]
+fn test_corrupt() {
+    let [marker, data] = Permalink::new("brainfuck", ",[.,]", "Hello!", "").encode();
+    assert_eq!(
+        Permalink::decode(&["~9", &data]),
+        Err(DecodeError::UnknownVersion("~9".to_string()))
+    );
+    // Cut at a whole number of base64 groups, so that what is left still decodes
+    let cut = (data.len() - 8) / 4 * 4;
+    assert_eq!(
+        Permalink::decode(&[&marker, &data[..cut]]),
+        Err(DecodeError::InvalidCompression)
+    );
+    assert_eq!(
+        Permalink::decode(&[&marker, "AAA"]),
+        Err(DecodeError::Truncated)
+    );
+
+    // Flip a bit of the decoded checksum rather than a character, since the last character
+    // may only carry padding bits
+    let mut bytes = BASE64URL_NOPAD.decode(data.as_bytes()).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    let data = BASE64URL_NOPAD.encode(&bytes);
+    assert_eq!(
+        Permalink::decode(&[&marker, &data]),
+        Err(DecodeError::ChecksumMismatch)
+    );
+}
+
+#[test]
+fn test_full_code() {
+    let mut permalink = Permalink::new("Deadfish", "s", "", "");
+    assert_eq!(permalink.full_code(), b"s");
+    permalink.header = "ii".to_string();
+    assert_eq!(permalink.full_code(), b"ii\ns");
+    permalink.footer = "o".to_string();
+    assert_eq!(permalink.full_code(), b"ii\ns\no");
+
+    assert_eq!(permalink.code_range(), 3..4);
+    permalink.header.clear();
+    assert_eq!(permalink.code_range(), 0..1);
+}
+
+#[test]
+fn test_check() {
+    let mut test = TestCase::new("", "");
+    assert_eq!(test.check(b"out"), None);
+    test.expected = Some("out\n".to_string());
+    assert_eq!(test.check(b"out"), Some(true));
+    assert_eq!(test.check(b"out\n\n"), Some(true));
+    assert_eq!(test.check(b"out2"), Some(false));
+    // Output is checked as written, not as shown
+    test.expected = Some("\u{fffd}".to_string());
+    assert_eq!(test.check(b"\xff"), Some(false));
+}
+
+#[test]
+fn test_link() {
+    let permalink = Permalink::new("brainfuck", ",[.,]", "", "");
+    let hash = permalink.encode().join("/");
+    assert_eq!(
+        permalink.to_link_at("http://localhost:8000/"),
+        format!("http://localhost:8000/#{}", hash)
+    );
+    assert_eq!(
+        permalink.to_link_at("https://example.com/tib/index.html#@YnJhaW5mdWNr"),
+        format!("https://example.com/tib/index.html#{}", hash)
+    );
+    assert!(permalink.to_link().starts_with(SITE_URL));
+}
+
+#[test]
+fn test_fingerprint() {
+    let permalink = Permalink::new("brainfuck", ",[.,]", "hi", "");
+    // FNV-1a of the language and the full code; must never change, since posts and tools
+    // rely on it
+    assert_eq!(permalink.fingerprint(), 0xd878_969c_f557_5fec);
+    assert_eq!(
+        permalink.fingerprint(),
+        Permalink::decode(&permalink.encode())
+            .unwrap()
+            .fingerprint()
+    );
+    // Test cases don't count, but the header does
+    assert_eq!(
+        permalink.fingerprint(),
+        Permalink::new("brainfuck", ",[.,]", "", "hi").fingerprint()
+    );
+    let mut with_header = permalink.clone();
+    with_header.header = "+".to_string();
+    assert_ne!(permalink.fingerprint(), with_header.fingerprint());
+    assert_ne!(
+        permalink.fingerprint(),
+        Permalink::new("brainfuck", ",[,.]", "hi", "").fingerprint()
+    );
+}
+
+#[test]
+fn test_v1() {
+    let hash_path = ["~1", "40wqSszMSytNzmbVidbTiWXKyGTSzWfMYGBgrGBgsq0AAASMTBw"];
+    let permalink = Permalink::decode(&hash_path).unwrap();
+    assert_eq!(
+        permalink,
+        Permalink {
+            header: "h".to_string(),
+            tests: vec![
+                TestCase::new("hi", "-o"),
+                TestCase {
+                    expected: Some("x".to_string()),
+                    ..TestCase::new("x", "")
+                },
+            ],
+            ..Permalink::new("brainfuck", ",[.,]", "", "")
+        }
+    );
+    assert_eq!(permalink.lang_version, "");
+    let upgraded = Permalink::decode(&permalink.encode()).unwrap();
+    assert_eq!(upgraded, permalink);
+    assert_eq!(upgraded.fingerprint(), permalink.fingerprint());
+}
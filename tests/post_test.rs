@@ -0,0 +1,126 @@
+use try_in_browser::post::{escape_html, render, selected_code, Post, Template};
+
+const POST: Post = Post {
+    lang: "brainfuck",
+    homepage: "https://esolangs.org/wiki/Brainfuck",
+    code: ",[.,]",
+    bytes: 5,
+    link: "https://try-in-browser.netlify.app/#~1/abc",
+    hash: 0xabc,
+};
+
+#[test]
+fn test_default() {
+    let post = render(Template::Default.text().unwrap(), &POST);
+    assert_eq!(
+        post,
+        "# [brainfuck][tib-brainfuck], 5 bytes\n\n```\n,[.,]\n```\n\n\
+         [Try in browser!][tib-0000000000000abc]\n\n\
+         [tib-brainfuck]: https://esolangs.org/wiki/Brainfuck\n\
+         [tib-0000000000000abc]: https://try-in-browser.netlify.app/#~1/abc\n"
+    );
+}
+
+#[test]
+fn test_builtin() {
+    for &template in &Template::ALL {
+        if let Some(text) = template.text() {
+            let post = render(text, &POST);
+            assert!(post.contains("brainfuck"));
+            assert!(post.contains("5 bytes"));
+            assert!(post.contains(POST.link));
+            assert!(!post.contains('{'));
+        }
+    }
+    assert!(
+        render(Template::Leaderboard.text().unwrap(), &POST).starts_with("# brainfuck, 5 bytes\n")
+    );
+    assert!(render(Template::Explanation.text().unwrap(), &POST).contains("## Explanation"));
+    assert_eq!(
+        Template::from_name(Template::Html.name()),
+        Some(Template::Html)
+    );
+    assert_eq!(Template::Custom.text(), None);
+}
+
+#[test]
+fn test_custom() {
+    let post = Post {
+        code: "<a>",
+        bytes: 3,
+        ..POST
+    };
+    assert_eq!(
+        render("{lang}: {bytes} byte{s}", &post),
+        "brainfuck: 3 bytes"
+    );
+    assert_eq!(render("{code_html} {code}", &post), "&lt;a&gt; <a>");
+    assert_eq!(render("{x} {{hash}} {", &post), "{x} {0000000000000abc} {");
+    let post = Post {
+        code: "+",
+        bytes: 1,
+        ..POST
+    };
+    assert_eq!(render("{bytes} byte{s}", &post), "1 byte");
+}
+
+#[test]
+fn test_fence() {
+    let post = Post {
+        code: "\n```\n\t`` ````\n",
+        bytes: 14,
+        ..POST
+    };
+    assert_eq!(
+        render("{bytes}\n{fence}\n{code}\n{fence}", &post),
+        "14\n`````\n\n```\n\t`` ````\n\n`````"
+    );
+    assert_eq!(render("{fence}", &POST), "```");
+    assert_eq!(
+        render("{code_indented}", &post),
+        "    \n    ```\n    \t`` ````\n    "
+    );
+    assert_eq!(render("{code_html}", &post), "\n```\n&#9;`` ````\n");
+}
+
+#[test]
+fn test_tabs() {
+    let post = Post {
+        code: "\t+\t",
+        bytes: 3,
+        ..POST
+    };
+    assert_eq!(render("{bytes}", &post), "3");
+    assert_eq!(render("{code}", &post), "\t+\t");
+    assert_eq!(render("{code_indented}", &post), "    \t+\t");
+    assert_eq!(render("{code_html}", &post), "&#9;+&#9;");
+}
+
+#[test]
+fn test_binary() {
+    // The code is shown as decoded, but counted as submitted
+    let submitted = b"\xff";
+    let post = Post {
+        code: &String::from_utf8_lossy(submitted),
+        bytes: submitted.len(),
+        ..POST
+    };
+    assert_eq!(render("{bytes} byte{s}: {code}", &post), "1 byte: \u{fffd}");
+}
+
+#[test]
+fn test_selected_code() {
+    let code = "a\u{1f41f}b\tc";
+    assert_eq!(selected_code(code, 0, 1), "a");
+    assert_eq!(selected_code(code, 1, 3), "\u{1f41f}");
+    assert_eq!(selected_code(code, 3, 100), "b\tc");
+    assert_eq!(selected_code(code, 4, 2), "");
+}
+
+#[test]
+fn test_escape_html() {
+    assert_eq!(
+        escape_html("<a href=\"x\">&\t</a>"),
+        "&lt;a href=&quot;x&quot;&gt;&amp;&#9;&lt;/a&gt;"
+    );
+}
@@ -0,0 +1,37 @@
+use try_in_browser::runtime::progress::{
+    Progress, ProgressTracker, CHECK_STEPS, HEARTBEAT_INTERVAL_MS,
+};
+
+#[test]
+fn test_progress() {
+    let mut tracker = ProgressTracker::new(0.0);
+    tracker.write(b"abc", b"");
+    tracker.write(b"d", b"ef");
+    assert!(!tracker.step(1, Some(7)));
+    assert_eq!(
+        tracker.progress(),
+        &Progress {
+            steps: 1,
+            stdout: 4,
+            stderr: 2,
+            ip: Some(7),
+        }
+    );
+}
+
+#[test]
+fn test_heartbeat() {
+    let mut tracker = ProgressTracker::new(0.0);
+    // The clock is only checked every so many steps
+    assert!(!tracker.step(CHECK_STEPS - 1, None));
+    assert!(tracker.step(CHECK_STEPS, None));
+    assert!(!tracker.step(CHECK_STEPS + 1, None));
+    assert!(tracker.step(CHECK_STEPS * 3, Some(2)));
+
+    assert_eq!(tracker.heartbeat(HEARTBEAT_INTERVAL_MS / 2.0), None);
+    let progress = tracker.heartbeat(HEARTBEAT_INTERVAL_MS).unwrap();
+    assert_eq!(progress.steps, CHECK_STEPS * 3);
+    assert_eq!(progress.ip, Some(2));
+    assert_eq!(tracker.heartbeat(HEARTBEAT_INTERVAL_MS * 1.5), None);
+    assert!(tracker.heartbeat(HEARTBEAT_INTERVAL_MS * 2.0).is_some());
+}
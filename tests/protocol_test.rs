@@ -0,0 +1,110 @@
+use try_in_browser::lang::Stats;
+use try_in_browser::runtime::progress::Progress;
+use try_in_browser::runtime::protocol::{
+    decode, decode_output, encode, encode_output, Job, ProtocolError, Request, Response,
+    PROTOCOL_VERSION,
+};
+
+#[test]
+fn test_roundtrip() {
+    let requests = [
+        Request::Init,
+        Request::Run(Job {
+            lang: "brainfuck".to_string(),
+            version: Some("1.0.0".to_string()),
+            code: b",[.,]".to_vec(),
+            input: vec![0xff, 0],
+            args: String::new(),
+        }),
+        Request::ProvideInput(b"more".to_vec()),
+        Request::Cancel,
+    ];
+    for request in &requests {
+        assert_eq!(decode::<Request>(&encode(request)).as_ref(), Ok(request));
+    }
+
+    let responses = [
+        Response::Ready,
+        Response::Output(vec![(b"out".to_vec(), b"err".to_vec())]),
+        Response::Heartbeat(Progress {
+            steps: 12345,
+            stdout: 6,
+            stderr: 0,
+            ip: Some(3),
+        }),
+        Response::Stats(Stats {
+            steps: 3,
+            ..Stats::default()
+        }),
+        Response::Finished,
+        Response::Failed("syntax error".to_string()),
+        Response::Error("oops".to_string()),
+    ];
+    for response in &responses {
+        assert_eq!(decode::<Response>(&encode(response)).as_ref(), Ok(response));
+    }
+    assert!(!Response::Output(vec![]).is_final());
+    assert!(!Response::Heartbeat(Progress::default()).is_final());
+    assert!(Response::Finished.is_final());
+}
+
+#[test]
+fn test_errors() {
+    assert!(matches!(
+        decode::<Request>("not json"),
+        Err(ProtocolError::Malformed(_))
+    ));
+    let unknown = format!(
+        r#"{{"version":{},"body":{{"type":"Explode"}}}}"#,
+        PROTOCOL_VERSION
+    );
+    assert!(matches!(
+        decode::<Request>(&unknown),
+        Err(ProtocolError::Malformed(_))
+    ));
+    let future = encode(&Request::Init).replacen(
+        &format!(r#""version":{}"#, PROTOCOL_VERSION),
+        r#""version":999"#,
+        1,
+    );
+    assert_eq!(
+        decode::<Request>(&future),
+        Err(ProtocolError::UnsupportedVersion(999))
+    );
+}
+
+#[test]
+fn test_output() {
+    let batches = [
+        vec![],
+        vec![(b"out".to_vec(), vec![])],
+        vec![
+            ("héllo\n".as_bytes().to_vec(), b"\0err".to_vec()),
+            (vec![], "🦀".as_bytes().to_vec()),
+        ],
+    ];
+    for batch in &batches {
+        assert_eq!(decode_output(&encode_output(batch)).as_ref(), Ok(batch));
+    }
+
+    let bytes = encode_output(&batches[2]);
+    let first = encode_output(&batches[2][..1].to_vec()).len();
+    for len in 0..bytes.len() {
+        // Cut between segments, the rest is simply missing
+        if len != 4 && len != first {
+            assert!(matches!(
+                decode_output(&bytes[..len]),
+                Err(ProtocolError::Malformed(_))
+            ));
+        }
+    }
+    // Output that is not UTF-8, or is cut in the middle of a character, is kept as it is
+    let binary = vec![(vec![b'a', 0xff], "🦀".as_bytes()[..2].to_vec())];
+    assert_eq!(decode_output(&encode_output(&binary)), Ok(binary));
+    let mut future = bytes;
+    future[..4].copy_from_slice(&999_u32.to_le_bytes());
+    assert_eq!(
+        decode_output(&future),
+        Err(ProtocolError::UnsupportedVersion(999))
+    );
+}
@@ -0,0 +1,210 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use try_in_browser::runtime::limits::{OutputLimits, OverflowPolicy};
+use try_in_browser::runtime::protocol::{Job, Request, Response};
+use try_in_browser::runtime::scheduler::{Outcome, Scheduler};
+use try_in_browser::runtime::transport::Transport;
+
+/// A worker whose responses are scripted by the test.
+#[derive(Clone, Default)]
+struct FakeWorker {
+    state: Rc<RefCell<FakeState>>,
+}
+
+#[derive(Default)]
+struct FakeState {
+    // The inputs of the jobs it was sent, in order
+    jobs: Vec<String>,
+    responses: VecDeque<Response>,
+    terminated: bool,
+}
+
+impl FakeWorker {
+    fn respond(&self, responses: Vec<Response>) {
+        self.state.borrow_mut().responses.extend(responses);
+    }
+
+    fn jobs(&self) -> Vec<String> {
+        self.state.borrow().jobs.clone()
+    }
+
+    fn is_terminated(&self) -> bool {
+        self.state.borrow().terminated
+    }
+}
+
+impl Transport for FakeWorker {
+    fn send(&mut self, request: Request) {
+        let mut state = self.state.borrow_mut();
+        assert!(!state.terminated);
+        if let Request::Run(job) = request {
+            state.jobs.push(String::from_utf8(job.input).unwrap());
+        }
+    }
+
+    fn try_recv(&mut self) -> Option<Response> {
+        self.state.borrow_mut().responses.pop_front()
+    }
+
+    fn terminate(&mut self) {
+        self.state.borrow_mut().terminated = true;
+    }
+}
+
+fn batch(inputs: &[&str]) -> Vec<Job> {
+    inputs
+        .iter()
+        .map(|input| Job {
+            lang: "brainfuck".to_string(),
+            code: b",[.,]".to_vec(),
+            input: input.as_bytes().to_vec(),
+            ..Job::default()
+        })
+        .collect()
+}
+
+fn output(out: &str) -> Response {
+    Response::Output(vec![(out.as_bytes().to_vec(), vec![])])
+}
+
+#[test]
+fn test_fan_out() {
+    let mut scheduler = Scheduler::new(batch(&["a", "b", "c"]), OutputLimits::default(), 2);
+    assert_eq!(scheduler.request_workers(), 2);
+    assert_eq!(scheduler.request_workers(), 0);
+    let workers = [FakeWorker::default(), FakeWorker::default()];
+    for worker in &workers {
+        assert!(scheduler.add_worker(worker.clone(), 0.0).is_none());
+    }
+    assert_eq!(workers[0].jobs(), ["a"]);
+    assert_eq!(workers[1].jobs(), ["b"]);
+
+    // Output streams in before the jobs end
+    workers[1].respond(vec![output("b")]);
+    assert_eq!(scheduler.poll(100.0), Vec::<usize>::new());
+    assert_eq!(scheduler.result(1).stdout, b"b");
+    assert_eq!(scheduler.result(1).outcome, None);
+
+    // The first worker to finish takes the last job
+    workers[1].respond(vec![Response::Finished]);
+    assert_eq!(scheduler.poll(200.0), [1]);
+    assert_eq!(scheduler.result(1).outcome, Some(Outcome::Finished));
+    assert!((scheduler.result(1).elapsed(1000.0) - 0.2).abs() < 1e-9);
+    assert_eq!(workers[1].jobs(), ["b", "c"]);
+
+    workers[0].respond(vec![output("a"), Response::Finished]);
+    workers[1].respond(vec![output("c"), Response::Finished]);
+    let mut ended = scheduler.poll(300.0);
+    ended.sort_unstable();
+    assert_eq!(ended, [0, 2]);
+    assert!(scheduler.is_done());
+    assert_eq!(scheduler.result(2).stdout, b"c");
+    assert_eq!(scheduler.take_idle_workers().len(), 2);
+    assert!(!workers.iter().any(FakeWorker::is_terminated));
+}
+
+#[test]
+fn test_crash() {
+    let mut scheduler = Scheduler::new(batch(&["a", "b"]), OutputLimits::default(), 1);
+    assert_eq!(scheduler.request_workers(), 1);
+    let crashing = FakeWorker::default();
+    assert!(scheduler.add_worker(crashing.clone(), 0.0).is_none());
+    crashing.respond(vec![output("a"), Response::Error("panicked".to_string())]);
+    assert_eq!(scheduler.poll(0.0), [0]);
+    assert_eq!(
+        scheduler.result(0).outcome,
+        Some(Outcome::Error("panicked".to_string()))
+    );
+    assert_eq!(scheduler.result(0).stdout, b"a");
+    // The worker didn't write the message, so it is added
+    assert_eq!(scheduler.result(0).stderr, b"\npanicked");
+    assert!(crashing.is_terminated());
+
+    // A replacement is needed for the remaining job
+    assert_eq!(scheduler.request_workers(), 1);
+    assert_eq!(scheduler.worker_failed(0.0), [1]);
+    assert!(matches!(
+        scheduler.result(1).outcome,
+        Some(Outcome::Error(_))
+    ));
+    assert_eq!(scheduler.result(1).stderr, b"\nno worker available");
+    assert!(scheduler.is_done());
+
+    // A program error doesn't cost the worker, and its message isn't repeated
+    let mut scheduler = Scheduler::new(batch(&["a", "b"]), OutputLimits::default(), 1);
+    assert_eq!(scheduler.request_workers(), 1);
+    let failing = FakeWorker::default();
+    assert!(scheduler.add_worker(failing.clone(), 0.0).is_none());
+    failing.respond(vec![
+        Response::Output(vec![(vec![], b"bad code".to_vec())]),
+        Response::Failed("bad code".to_string()),
+    ]);
+    assert_eq!(scheduler.poll(0.0), [0]);
+    assert_eq!(
+        scheduler.result(0).outcome,
+        Some(Outcome::Failed("bad code".to_string()))
+    );
+    assert_eq!(scheduler.result(0).stderr, b"bad code");
+    assert!(!failing.is_terminated());
+    assert_eq!(failing.jobs(), ["a", "b"]);
+}
+
+#[test]
+fn test_limits() {
+    let limits = OutputLimits {
+        stdout: 3,
+        ..OutputLimits::default()
+    };
+    let mut scheduler = Scheduler::new(batch(&["a", "b"]), limits, 2);
+    assert_eq!(scheduler.request_workers(), 2);
+    let workers = [FakeWorker::default(), FakeWorker::default()];
+    for worker in &workers {
+        assert!(scheduler.add_worker(worker.clone(), 0.0).is_none());
+    }
+    workers[0].respond(vec![output("ab"), output("cd"), Response::Finished]);
+    assert_eq!(scheduler.poll(0.0), [0]);
+    assert_eq!(scheduler.result(0).outcome, Some(Outcome::OutputLimit));
+    assert_eq!(scheduler.result(0).stdout, b"abc");
+    assert!(workers[0].is_terminated());
+    assert!(!workers[1].is_terminated());
+    workers[1].respond(vec![output("b"), Response::Finished]);
+    assert_eq!(scheduler.poll(0.0), [1]);
+    assert!(!scheduler.result(1).is_truncated());
+
+    let limits = OutputLimits {
+        policy: OverflowPolicy::KeepTail,
+        ..limits
+    };
+    let mut scheduler = Scheduler::new(batch(&["a"]), limits, 1);
+    assert_eq!(scheduler.request_workers(), 1);
+    let worker = FakeWorker::default();
+    assert!(scheduler.add_worker(worker.clone(), 0.0).is_none());
+    worker.respond(vec![output("ab"), output("cd"), Response::Finished]);
+    assert_eq!(scheduler.poll(0.0), [0]);
+    assert_eq!(scheduler.result(0).outcome, Some(Outcome::Finished));
+    assert_eq!(scheduler.result(0).stdout, b"bcd");
+    // The job finished, but its output can't be checked
+    assert!(scheduler.result(0).is_truncated());
+}
+
+#[test]
+fn test_cancel() {
+    let mut scheduler = Scheduler::new(batch(&["a", "b", "c"]), OutputLimits::default(), 2);
+    assert_eq!(scheduler.request_workers(), 2);
+    let worker = FakeWorker::default();
+    assert!(scheduler.add_worker(worker.clone(), 0.0).is_none());
+    assert_eq!(scheduler.cancel(10.0), [0, 1, 2]);
+    assert!(worker.is_terminated());
+    assert!(scheduler.is_done());
+    for job in 0..3 {
+        assert_eq!(scheduler.result(job).outcome, Some(Outcome::Cancelled));
+    }
+    assert_eq!(scheduler.result(1).elapsed(20.0), 0.0);
+
+    // A worker that arrives too late is handed back
+    let late = FakeWorker::default();
+    assert!(scheduler.add_worker(late.clone(), 20.0).is_some());
+    assert_eq!(late.jobs(), Vec::<String>::new());
+    assert_eq!(scheduler.request_workers(), 0);
+}